@@ -0,0 +1,761 @@
+//! Asynchronous, [tokio](https://tokio.rs) based counterparts of [`Uhppoted`](crate::Uhppoted)
+//! and [`Device`](crate::Device).
+//!
+//! An [`AsyncUhppoted`] wraps an [`Uhppoted`], so its requests share the socket,
+//! [`RetryPolicy`](crate::RetryPolicy), [`Transport`](crate::Transport), registered
+//! [`Controller`](crate::Controller)s and [`RequestMetrics`] of that [`Uhppoted`]. Create one from
+//! a configured [`Uhppoted`] with [`From`]. Every operation is an `async fn`, and must be awaited
+//! within a tokio runtime.
+//!
+//! Replies on the UDP socket are awaited without blocking a thread of the runtime, and retries
+//! back off with `tokio::time::sleep`. [`Transport`](crate::Transport)s are blocking, so requests
+//! through a [`Transport`](crate::Transport), such as that of a [`Controller`](crate::Controller)
+//! reached over TCP, run on tokio's pool of blocking threads instead.
+//!
+//! Example:
+//! ```no_run
+//! # async fn run() -> uhppote_rs::Result<()> {
+//! use uhppote_rs::AsyncUhppoted;
+//! let uhppoted = AsyncUhppoted::default();
+//! let device = uhppoted.get_device(423196779, None);
+//! let status = device.get_status().await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::cards::{DELETED_CARD, NO_CARD};
+use crate::listener::decode_status;
+use crate::messages::types::DateBCD;
+use crate::messages::*;
+use crate::types::*;
+use crate::{check_card, check_delay, check_door, check_passcodes, Error, Result};
+use crate::{Device, RequestMetrics, Uhppoted, UHPPOTE_PORT};
+use chrono::Datelike;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+#[derive(Debug)]
+pub struct AsyncUhppoted {
+    u: Arc<Uhppoted>,
+}
+
+impl AsyncUhppoted {
+    /// Create a new AsyncUhppoted struct
+    ///
+    /// Example:
+    /// ```no_run
+    /// use uhppote_rs::AsyncUhppoted;
+    /// let uhppoted = AsyncUhppoted::new(
+    ///     "0.0.0.0:0".parse().unwrap(),
+    ///     "255.255.255.255".parse().unwrap(),
+    ///     std::time::Duration::new(5, 0),
+    /// );
+    pub fn new(bind: SocketAddr, broadcast: Ipv4Addr, timeout: Duration) -> AsyncUhppoted {
+        Uhppoted::new(bind, broadcast, timeout).into()
+    }
+
+    /// [`RequestMetrics`] of the requests sent through this [`AsyncUhppoted`], see
+    /// [`Uhppoted::request_metrics`].
+    pub fn request_metrics(&self) -> BTreeMap<String, RequestMetrics> {
+        self.u.request_metrics()
+    }
+
+    /// Get all the available [`DeviceConfig`]s on the local network. This broadcasts a discovery message
+    /// and waits [`AsyncUhppoted::timeout`] for responses.
+    pub async fn get_device_configs(&self) -> Result<Vec<DeviceConfig>> {
+        let request = GetConfigRequest::new(0);
+        let response: Vec<GetConfigResponse> = broadcast_and_receive(request, self).await?;
+        response.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    /// Get all the available [`AsyncDevice`]s on the local network. This broadcasts a discovery
+    /// message and waits [`AsyncUhppoted::timeout`] for responses.
+    pub async fn get_devices(&self) -> Result<Vec<AsyncDevice<'_>>> {
+        let request = GetConfigRequest::new(0);
+        let response: Vec<GetConfigResponse> = broadcast_and_receive(request, self).await?;
+        let r = response
+            .into_iter()
+            .map(|r| AsyncDevice::new(self, r.device_id, Some(r.ip_address)))
+            .collect();
+        Ok(r)
+    }
+
+    /// Get an [`AsyncDevice`] by its device ID. This does not check if the device actually exists,
+    /// but merely represents a device to interact with. The address is resolved like
+    /// [`Uhppoted::get_device`] does.
+    pub fn get_device(&self, id: u32, ip_address: Option<Ipv4Addr>) -> AsyncDevice<'_> {
+        AsyncDevice::new(self, id, ip_address)
+    }
+
+    /// Listen for incoming [`Status`] messages from the UHPPOTE system on a specific `address`.
+    /// Messages that can't be decoded are skipped.
+    pub async fn listen<F: FnMut(Status)>(
+        &self,
+        address: SocketAddr,
+        mut handler: F,
+    ) -> Result<()> {
+        let socket = UdpSocket::bind(address).await?;
+        socket.set_broadcast(true)?;
+        loop {
+            let mut buf = [0u8; 64];
+            if socket.recv(&mut buf).await? != buf.len() {
+                continue;
+            }
+            if let Some(Ok(status)) = decode_status(&buf, &[]) {
+                handler(status);
+            }
+        }
+    }
+}
+
+impl From<Uhppoted> for AsyncUhppoted {
+    /// Send requests through `u`, with its socket, retry policy, transport and registered
+    /// controllers.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use uhppote_rs::{AsyncUhppoted, RetryPolicy, Uhppoted};
+    /// let uhppoted = AsyncUhppoted::from(Uhppoted::default().retry_policy(RetryPolicy {
+    ///     attempts: 3,
+    ///     ..Default::default()
+    /// }));
+    /// ```
+    fn from(u: Uhppoted) -> AsyncUhppoted {
+        AsyncUhppoted { u: Arc::new(u) }
+    }
+}
+
+impl Default for AsyncUhppoted {
+    /// Creates a default instance of [`AsyncUhppoted`], using the same defaults as
+    /// [`Uhppoted::default`](crate::Uhppoted::default).
+    fn default() -> AsyncUhppoted {
+        AsyncUhppoted::new(
+            "0.0.0.0:0".parse().unwrap(),
+            "255.255.255.255".parse().unwrap(),
+            Duration::new(5, 0),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct AsyncDevice<'a> {
+    u: &'a AsyncUhppoted,
+    id: u32,
+    ip_address: Option<Ipv4Addr>,
+}
+
+impl<'a> AsyncDevice<'a> {
+    /// Create a new [`AsyncDevice`] from an [`AsyncUhppoted`] and a device ID.
+    fn new(u: &'a AsyncUhppoted, id: u32, ip_address: Option<Ipv4Addr>) -> AsyncDevice<'a> {
+        AsyncDevice { u, id, ip_address }
+    }
+
+    /// Enable or disable the keypads of the readers of doors 1-4.
+    pub async fn activate_keypads(&self, keypads: [bool; 4]) -> Result<()> {
+        let request =
+            ActivateKeypadsRequest::new(self.id, keypads[0], keypads[1], keypads[2], keypads[3]);
+        let response: ActivateKeypadsResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "ActivateKeypads",
+            })
+        }
+    }
+
+    /// Add a [`Card`] to the [`AsyncDevice`].
+    pub async fn add_card(&self, card: Card) -> Result<()> {
+        check_card(&card)?;
+        let request = PutCardRequest::new(
+            self.id,
+            card.number,
+            card.from.try_into()?,
+            card.to.try_into()?,
+            card.doors[0],
+            card.doors[1],
+            card.doors[2],
+            card.doors[3],
+            card.pin.into(),
+        );
+        let response: PutCardResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "PutCard",
+            })
+        }
+    }
+
+    /// Add a [`Task`] to the system.
+    pub async fn add_task(&self, task: Task) -> Result<()> {
+        let request = AddTaskRequest::new(
+            self.id,
+            DateBCD::new(
+                task.from.year() as u16,
+                task.from.month() as u8,
+                task.from.day() as u8,
+            ),
+            DateBCD::new(
+                task.to.year() as u16,
+                task.to.month() as u8,
+                task.to.day() as u8,
+            ),
+            task.monday,
+            task.tuesday,
+            task.wednesday,
+            task.thursday,
+            task.friday,
+            task.saturday,
+            task.sunday,
+            task.at.try_into()?,
+            task.door,
+            task.task as u8,
+            task.more_cards,
+        );
+
+        let response: AddTaskResponse = send_and_receive(request, self).await?;
+
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "AddTask",
+            })
+        }
+    }
+
+    /// Remove all [`Card`]s from the [`AsyncDevice`].
+    pub async fn clear_cards(&self) -> Result<()> {
+        let magic_word = 0x55aaaa55;
+        let request = DeleteCardsRequest::new(self.id, magic_word);
+        let response: DeleteCardsResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "DeleteCards",
+            })
+        }
+    }
+
+    /// Remove all [`Task`]s from the [`AsyncDevice`].
+    pub async fn clear_tasks(&self) -> Result<()> {
+        let request = ClearTaskListRequest::new(self.id, 0x55aaaa55);
+        let response: ClearTaskListResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "ClearTaskList",
+            })
+        }
+    }
+
+    /// Remove all [`TimeProfile`]s from the [`AsyncDevice`].
+    pub async fn clear_time_profiles(&self) -> Result<()> {
+        let magic_word = 0x55aaaa55;
+        let request = ClearTimeProfilesRequest::new(self.id, magic_word);
+        let response: ClearTimeProfilesResponse = send_and_receive(request, self).await?;
+        if response.magic_word == magic_word {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "ClearTimeProfiles",
+            })
+        }
+    }
+
+    /// Remove a [`Card`] from the [`AsyncDevice`].
+    pub async fn delete_card(&self, number: u32) -> Result<()> {
+        let request = DeleteCardRequest::new(self.id, number);
+        let response: DeleteCardResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "DeleteCard",
+            })
+        }
+    }
+
+    /// Get the [`AntiPassback`] mode of the [`AsyncDevice`].
+    pub async fn get_antipassback(&self) -> Result<AntiPassback> {
+        let request = GetAntiPassbackRequest::new(self.id);
+        let response: GetAntiPassbackResponse = send_and_receive(request, self).await?;
+        response.antipassback.try_into()
+    }
+
+    /// Get a specific [`Card`] by its ID.
+    pub async fn get_card_by_id(&self, id: u32) -> Result<Card> {
+        let request = GetCardByIDRequest::new(self.id, id);
+        let response: GetCardByIDResponse = send_and_receive(request, self).await?;
+        response.try_into()
+    }
+
+    /// Get a specific [`Card`] by its index.
+    pub async fn get_card_by_index(&self, index: u32) -> Result<Card> {
+        let request = GetCardByIndexRequest::new(self.id, index);
+        let response: GetCardByIndexResponse = send_and_receive(request, self).await?;
+        response.try_into()
+    }
+
+    /// Get all [`Card`]s stored on the [`AsyncDevice`], skipping deleted cards like
+    /// [`Cards`](crate::Cards) does.
+    pub async fn get_all_cards(&self) -> Result<Vec<Card>> {
+        let total = self.get_cards().await?;
+        let mut cards = Vec::new();
+        let mut index = 0;
+        while (cards.len() as u32) < total {
+            index += 1;
+            let request = GetCardByIndexRequest::new(self.id, index);
+            let response: GetCardByIndexResponse = send_and_receive(request, self).await?;
+            match response.card_number {
+                NO_CARD => break,
+                DELETED_CARD => continue,
+                _ => cards.push(response.try_into()?),
+            }
+        }
+        Ok(cards)
+    }
+
+    /// Get the number of [`Card`]s from the [`AsyncDevice`].
+    pub async fn get_cards(&self) -> Result<u32> {
+        let request = GetCardsRequest::new(self.id);
+        let response: GetCardsResponse = send_and_receive(request, self).await?;
+        Ok(response.records)
+    }
+
+    /// Get a [`DeviceConfig`] for a the [`AsyncDevice`].
+    pub async fn get_config(&self) -> Result<DeviceConfig> {
+        let request = GetConfigRequest::new(self.id);
+        let response: GetConfigResponse = send_and_receive(request, self).await?;
+        response.try_into()
+    }
+
+    /// Get a [`DoorControl`] for a specific door.
+    /// Note that doors are addressed 1-4, not 0-3.
+    pub async fn get_door_control(&self, door: u8) -> Result<DoorControl> {
+        let request = GetDoorControlStateRequest::new(self.id, door);
+        let response: GetDoorControlStateResponse = send_and_receive(request, self).await?;
+        Ok(response.into())
+    }
+
+    /// Get an [`Event`] by its index.
+    pub async fn get_event(&self, index: u32) -> Result<Event> {
+        let request = GetEventRequest::new(self.id, index);
+        let response: GetEventResponse = send_and_receive(request, self).await?;
+        response.try_into()
+    }
+
+    /// Get the event index the [`AsyncDevice`]
+    pub async fn get_event_index(&self) -> Result<u32> {
+        let request = GetEventIndexRequest::new(self.id);
+        let response: GetEventIndexResponse = send_and_receive(request, self).await?;
+        Ok(response.index)
+    }
+
+    /// Get what listener (IP:PORT) is set on the [`AsyncDevice`].
+    pub async fn get_listener(&self) -> Result<SocketAddr> {
+        let request = GetListenerRequest::new(self.id);
+        let response: GetListenerResponse = send_and_receive(request, self).await?;
+        Ok(SocketAddr::from((response.ip_address, response.port)))
+    }
+
+    /// Get the [`Status`] of the [`AsyncDevice`].
+    pub async fn get_status(&self) -> Result<Status> {
+        let request = GetStatusRequest::new(self.id);
+        let response: GetStatusResponse = send_and_receive(request, self).await?;
+        let status: Status = response.try_into()?;
+        Ok(status)
+    }
+
+    /// Get the current time of the [`AsyncDevice`].
+    pub async fn get_time(&self) -> Result<NaiveDateTime> {
+        let request = GetTimeRequest::new(self.id);
+        let response: GetTimeResponse = send_and_receive(request, self).await?;
+        response.datetime.try_into()
+    }
+
+    /// Get the [`TimeProfile`] by ID.
+    pub async fn get_time_profile(&self, profile_id: u8) -> Result<TimeProfile> {
+        let request = GetTimeProfileRequest::new(self.id, profile_id);
+        let response: GetTimeProfileResponse = send_and_receive(request, self).await?;
+        response.try_into()
+    }
+
+    /// Open a door.
+    /// Note that doors are addressed 1-4, not 0-3.
+    pub async fn open_door(&self, door: u8) -> Result<()> {
+        let request = OpenDoorRequest::new(self.id, door);
+        let response: OpenDoorResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "OpenDoor",
+            })
+        }
+    }
+
+    /// Refresh the task list of the [`AsyncDevice`].
+    pub async fn refresh_task_list(&self) -> Result<()> {
+        let request = RefreshTaskListRequest::new(self.id, 0x55aaaa55);
+        let response: RefreshTaskListResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "RefreshTaskList",
+            })
+        }
+    }
+
+    /// Set the [`AntiPassback`] mode of the [`AsyncDevice`].
+    pub async fn set_antipassback(&self, antipassback: AntiPassback) -> Result<()> {
+        let request = SetAntiPassbackRequest::new(self.id, antipassback as u8);
+        let response: SetAntiPassbackResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetAntiPassback",
+            })
+        }
+    }
+
+    /// Set the [`DoorControl`] for a specific door.
+    /// Note that the delay is in seconds and can maximally be 255.
+    pub async fn set_door_control_state(
+        &self,
+        door: u8,
+        state: DoorControl,
+    ) -> Result<DoorControl> {
+        check_delay(&state)?;
+        let request = SetDoorControlStateRequest::new(
+            self.id,
+            door,
+            state.mode as u8,
+            state.delay.as_secs() as u8,
+        );
+        let response: SetDoorControlStateResponse = send_and_receive(request, self).await?;
+        Ok(response.into())
+    }
+
+    /// Set the (up to four) supervisor passcodes of a door. Passcodes are at most 6 digits; a
+    /// passcode of 0 means "unused".
+    /// Note that doors are addressed 1-4, not 0-3.
+    pub async fn set_door_passcodes(&self, door: u8, passcodes: [u32; 4]) -> Result<()> {
+        check_passcodes(door, &passcodes)?;
+        let request = SetDoorPasscodesRequest::new(
+            self.id,
+            door,
+            passcodes[0],
+            passcodes[1],
+            passcodes[2],
+            passcodes[3],
+        );
+        let response: SetDoorPasscodesResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetDoorPasscodes",
+            })
+        }
+    }
+
+    /// Set the event index the [`AsyncDevice`] will use.
+    pub async fn set_event_index(&self, index: u32) -> Result<()> {
+        let request = SetEventIndexRequest::new(self.id, index, 0x55aaaa55);
+        let response: SetEventIndexResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetEventIndex",
+            })
+        }
+    }
+
+    /// Set the [`FirstCardConfig`] of a door.
+    /// Note that doors are addressed 1-4, not 0-3.
+    pub async fn set_first_card(&self, config: FirstCardConfig) -> Result<()> {
+        check_door(config.door)?;
+        let request = SetFirstCardRequest::new(
+            self.id,
+            config.door,
+            config.start.try_into()?,
+            config.start_mode as u8,
+            config.end.try_into()?,
+            config.end_mode as u8,
+            config.monday,
+            config.tuesday,
+            config.wednesday,
+            config.thursday,
+            config.friday,
+            config.saturday,
+            config.sunday,
+        );
+        let response: SetFirstCardResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetFirstCard",
+            })
+        }
+    }
+
+    /// Set the door [`Interlock`] of the [`AsyncDevice`].
+    pub async fn set_interlock(&self, interlock: Interlock) -> Result<()> {
+        let request = SetInterlockRequest::new(self.id, interlock as u8);
+        let response: SetInterlockResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetInterlock",
+            })
+        }
+    }
+
+    /// Set the listener (IP:PORT) the [`AsyncDevice`] will use to send [`Status`] messages to.
+    pub async fn set_listener(&self, address: Ipv4Addr, port: u16) -> Result<()> {
+        let request = SetListenerRequest::new(self.id, address, port);
+        let response: SetListenerResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetListener",
+            })
+        }
+    }
+
+    /// Set IP address, subnet mask and gateway for the [`AsyncDevice`].
+    pub async fn set_network_config(
+        &self,
+        address: Ipv4Addr,
+        subnet: Ipv4Addr,
+        gateway: Ipv4Addr,
+    ) -> Result<()> {
+        let request = SetAddressRequest::new(self.id, address, subnet, gateway, 0x55aaaa55);
+
+        send(request, self).await
+    }
+
+    /// Enable the recording of special events.
+    pub async fn enable_record_special_events(&self, enable: bool) -> Result<()> {
+        let request = SetRecordSpecialEventsRequest::new(self.id, enable);
+        let response: SetRecordSpecialEventsResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetRecordSpecialEvents",
+            })
+        }
+    }
+
+    /// Set the local time of the [`AsyncDevice`].
+    pub async fn set_time(&self, datetime: NaiveDateTime) -> Result<NaiveDateTime> {
+        let request = SetTimeRequest::new(self.id, datetime.try_into()?);
+        let response: SetTimeResponse = send_and_receive(request, self).await?;
+        response.datetime.try_into()
+    }
+
+    /// Add or update new [`TimeProfile`] to the [`AsyncDevice`].
+    pub async fn add_or_update_time_profile(&self, profile: TimeProfile) -> Result<()> {
+        let request = SetTimeProfileRequest::new(
+            self.id,
+            profile.id,
+            profile.from.try_into()?,
+            profile.to.try_into()?,
+            profile.monday,
+            profile.tuesday,
+            profile.wednesday,
+            profile.thursday,
+            profile.friday,
+            profile.saturday,
+            profile.sunday,
+            profile.segments[0].start.try_into()?,
+            profile.segments[0].end.try_into()?,
+            profile.segments[1].start.try_into()?,
+            profile.segments[1].end.try_into()?,
+            profile.segments[2].start.try_into()?,
+            profile.segments[2].end.try_into()?,
+            profile.linked_profile_id,
+        );
+        let response: SetTimeProfileResponse = send_and_receive(request, self).await?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetTimeProfile",
+            })
+        }
+    }
+}
+
+/// Send a [`Request`] and receive a [`Response`] through the [`Uhppoted`] of the
+/// [`AsyncDevice`], retrying according to its [`RetryPolicy`](crate::RetryPolicy).
+async fn send_and_receive<T, S>(request: T, d: &AsyncDevice<'_>) -> Result<S>
+where
+    T: Request + Send + 'static,
+    S: Response + Debug + Send + 'static,
+{
+    let device = d.u.u.get_device(d.id, d.ip_address);
+    if has_transport(&device) {
+        let (u, id, ip_address) = (d.u.u.clone(), d.id, d.ip_address);
+        return blocking(move || crate::send_and_receive(request, &u.get_device(id, ip_address)))
+            .await;
+    }
+
+    let policy = &d.u.u.retry_policy;
+    let attempts = policy.attempts(request.get_message_type());
+    let timeout = device
+        .timeout
+        .or(policy.attempt_timeout)
+        .unwrap_or(d.u.u.timeout);
+    let mut attempt = 1;
+    loop {
+        match attempt_send_and_receive(&request, &device, timeout).await {
+            Err(e) if e.is_transient() && attempt < attempts => {
+                tokio::time::sleep(policy.backoff(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) if attempt > 1 => {
+                return Err(Error::Retried {
+                    attempts: attempt,
+                    error: Box::new(e),
+                })
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Send a [`Request`] once over UDP and receive a [`Response`]. Datagrams that are not a reply
+/// to the request are discarded until `timeout` expires.
+async fn attempt_send_and_receive<T, S>(request: &T, d: &Device<'_>, timeout: Duration) -> Result<S>
+where
+    T: Request,
+    S: Response + Debug,
+{
+    let metrics = &d.u.metrics;
+    let message_type = request.get_message_type();
+    let dispatcher = d.u.dispatcher()?;
+    let sent = Instant::now();
+    metrics.record(message_type, |m| m.requests += 1);
+
+    let result = async {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut replies = dispatcher
+            .subscribe_async(d.id, message_type, timeout)
+            .await?;
+        dispatcher.send_to(&request.to_bytes(), address(d))?;
+        while let Ok(Some(datagram)) = tokio::time::timeout_at(deadline, replies.recv()).await {
+            match decode_reply(request, &datagram) {
+                Ok(Some(response)) => return Ok(response),
+                Ok(None) => {}
+                Err(_) => metrics.record(message_type, |m| m.decode_errors += 1),
+            }
+        }
+        Err(Error::Timeout)
+    }
+    .await;
+
+    match &result {
+        Ok(_) => metrics.record(message_type, |m| {
+            m.responses += 1;
+            m.latency += sent.elapsed();
+        }),
+        Err(Error::Timeout) => metrics.record(message_type, |m| m.timeouts += 1),
+        Err(_) => {}
+    }
+    result
+}
+
+/// Send a [`Request`] to the [`AsyncDevice`], but don't expect a response.
+async fn send<T: Request + Send + 'static>(request: T, d: &AsyncDevice<'_>) -> Result<()> {
+    let device = d.u.u.get_device(d.id, d.ip_address);
+    if has_transport(&device) {
+        let (u, id, ip_address) = (d.u.u.clone(), d.id, d.ip_address);
+        return blocking(move || crate::send(request, &u.get_device(id, ip_address))).await;
+    }
+    d.u.u
+        .dispatcher()?
+        .send_to(&request.to_bytes(), address(&device))?;
+    d.u.u
+        .metrics
+        .record(request.get_message_type(), |m| m.requests += 1);
+    Ok(())
+}
+
+/// Broadcast a [`Request`] to all [`AsyncDevice`]s and collect the replies that arrive within
+/// the timeout of the [`Uhppoted`].
+async fn broadcast_and_receive<T, S>(request: T, u: &AsyncUhppoted) -> Result<Vec<S>>
+where
+    T: Request + Send + 'static,
+    S: Response + Debug + Send + 'static,
+{
+    let u = &u.u;
+    if u.transport.is_some() {
+        let u = u.clone();
+        return blocking(move || crate::broadcast_and_receive(request, &u)).await;
+    }
+
+    let message_type = request.get_message_type();
+    let dispatcher = u.dispatcher()?;
+    u.metrics.record(message_type, |m| m.requests += 1);
+
+    let deadline = tokio::time::Instant::now() + u.timeout;
+    let mut replies = dispatcher
+        .subscribe_async(0, message_type, u.timeout)
+        .await?;
+    dispatcher.send_to(
+        &request.to_bytes(),
+        SocketAddr::new(u.broadcast_address.into(), UHPPOTE_PORT),
+    )?;
+    let mut ret = Vec::new();
+    while let Ok(Some(datagram)) = tokio::time::timeout_at(deadline, replies.recv()).await {
+        match decode_reply(&request, &datagram) {
+            Ok(response) => ret.extend(response),
+            Err(_) => u.metrics.record(message_type, |m| m.decode_errors += 1),
+        }
+    }
+    Ok(ret)
+}
+
+/// Whether requests for `d` go through a [`Transport`](crate::Transport) instead of the UDP
+/// socket of its [`Uhppoted`].
+fn has_transport(d: &Device) -> bool {
+    d.transport.is_some() || d.u.transport.is_some()
+}
+
+/// The UDP address of `d`: its own, or the broadcast address.
+fn address(d: &Device) -> SocketAddr {
+    SocketAddr::new(crate::get_address(d).into(), d.port)
+}
+
+/// Run `f` on tokio's pool of blocking threads. [`Transport`](crate::Transport)s are blocking, so
+/// requests through them can't be awaited otherwise.
+async fn blocking<R, F>(f: F) -> Result<R>
+where
+    R: Send + 'static,
+    F: FnOnce() -> Result<R> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(Error::Io(io::Error::other(e))),
+    }
+}
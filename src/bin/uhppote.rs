@@ -0,0 +1,453 @@
+//! Command line tool to interact with UHPPOTE controllers.
+//!
+//! Example:
+//! ```sh
+//! uhppote discover
+//! uhppote discover --range 10.20.0.1-10.20.0.254
+//! uhppote --ip 192.168.1.100 get-status 423196779
+//! uhppote --json list-cards 423196779
+//! ```
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use clap::{Args, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use uhppote_rs::*;
+
+#[derive(Parser)]
+#[command(
+    name = "uhppote",
+    version,
+    about = "Interact with UHPPOTE access controllers"
+)]
+struct Cli {
+    /// IP address of the controller. Without it, requests are broadcast.
+    #[arg(long, global = true)]
+    ip: Option<Ipv4Addr>,
+
+    /// Broadcast address used for discovery and to reach controllers without --ip.
+    #[arg(long, global = true, default_value = "255.255.255.255")]
+    broadcast: Ipv4Addr,
+
+    /// Local address to bind to.
+    #[arg(long, global = true, default_value = "0.0.0.0:0")]
+    bind: SocketAddr,
+
+    /// How long to wait for a response, in seconds.
+    #[arg(long, global = true, default_value_t = 5.0)]
+    timeout: f64,
+
+    /// How often to send requests that only read from the device before giving up.
+    #[arg(long, global = true, default_value_t = 3)]
+    attempts: u32,
+
+    /// Print JSON instead of a table.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct Target {
+    /// ID (serial number) of the controller.
+    device_id: u32,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Find controllers on all networks this host is attached to.
+    Discover {
+        /// Also probe the addresses in a range, like 10.20.0.1-10.20.0.254, for controllers that
+        /// broadcasts don't reach. Can be repeated.
+        #[arg(long, value_parser = parse_range)]
+        range: Vec<RangeInclusive<Ipv4Addr>>,
+    },
+    /// Get the network configuration and firmware version of a controller.
+    GetConfig(Target),
+    /// Get the status of a controller.
+    GetStatus(Target),
+    /// Get the local time of a controller.
+    GetTime(Target),
+    /// Set the local time of a controller.
+    SetTime {
+        #[command(flatten)]
+        target: Target,
+        /// Time to set, like 2023-05-04T12:34:56. Defaults to the current local time.
+        datetime: Option<NaiveDateTime>,
+    },
+    /// Open a door (1-4).
+    OpenDoor {
+        #[command(flatten)]
+        target: Target,
+        door: u8,
+    },
+    /// Get a card by its number.
+    GetCard {
+        #[command(flatten)]
+        target: Target,
+        number: u32,
+    },
+    /// Add or update a card.
+    PutCard {
+        #[command(flatten)]
+        target: Target,
+        number: u32,
+        /// First day the card is valid, like 2023-01-01.
+        #[arg(long)]
+        from: NaiveDate,
+        /// Last day the card is valid, like 2023-12-31.
+        #[arg(long)]
+        to: NaiveDate,
+        /// Permissions for doors 1-4: 0 (no access), 1 (access) or a time profile (2-254).
+        #[arg(long, value_delimiter = ',', default_value = "1,1,1,1")]
+        doors: Vec<u8>,
+        /// PIN for doors that require a card and a PIN.
+        #[arg(long)]
+        pin: Option<u32>,
+    },
+    /// Delete a card.
+    DeleteCard {
+        #[command(flatten)]
+        target: Target,
+        number: u32,
+    },
+    /// Delete all cards.
+    DeleteAllCards(Target),
+    /// List all cards.
+    ListCards(Target),
+    /// Get a single event by its index.
+    GetEvent {
+        #[command(flatten)]
+        target: Target,
+        index: u32,
+    },
+    /// Get the events after an index.
+    ///
+    /// Without --after, get the events after the controller's event index.
+    GetEvents {
+        #[command(flatten)]
+        target: Target,
+        #[arg(long)]
+        after: Option<u32>,
+        /// Set the controller's event index to the last event fetched.
+        #[arg(long)]
+        commit: bool,
+    },
+    /// Get the address events are sent to.
+    GetListener(Target),
+    /// Set the address events are sent to, like 192.168.1.10:60001.
+    SetListener {
+        #[command(flatten)]
+        target: Target,
+        address: SocketAddr,
+    },
+    /// Get the control mode and open delay of a door (1-4).
+    GetDoorControl {
+        #[command(flatten)]
+        target: Target,
+        door: u8,
+    },
+    /// Set the control mode and open delay of a door (1-4).
+    SetDoorControl {
+        #[command(flatten)]
+        target: Target,
+        door: u8,
+        /// normally_open, normally_closed or controlled.
+        #[arg(value_parser = parse_enum::<DoorControlMode>)]
+        mode: DoorControlMode,
+        /// Open delay in seconds.
+        delay: u8,
+    },
+    /// Get a time profile (2-254).
+    GetTimeProfile {
+        #[command(flatten)]
+        target: Target,
+        profile_id: u8,
+    },
+    /// Add or update a time profile, given as JSON.
+    PutTimeProfile {
+        #[command(flatten)]
+        target: Target,
+        #[arg(value_parser = parse_json::<TimeProfile>)]
+        profile: TimeProfile,
+    },
+    /// Delete all time profiles.
+    ClearTimeProfiles(Target),
+    /// Add a task, given as JSON. Use refresh-tasks to activate it.
+    AddTask {
+        #[command(flatten)]
+        target: Target,
+        #[arg(value_parser = parse_json::<Task>)]
+        task: Task,
+    },
+    /// Delete all tasks.
+    ClearTasks(Target),
+    /// Activate the tasks that were added.
+    RefreshTasks(Target),
+    /// Register as the listener of devices and print their events as JSON lines.
+    Listen {
+        /// Local address to receive events on.
+        #[arg(long, default_value = "0.0.0.0:60001")]
+        listen: SocketAddr,
+        /// Address the devices should send events to. Defaults to --listen.
+        #[arg(long)]
+        advertise: Option<SocketAddr>,
+        /// Device to forward events from, as ID or ID@IP. Can be repeated.
+        #[arg(long = "device", required = true, value_parser = parse_device)]
+        devices: Vec<(u32, Option<Ipv4Addr>)>,
+        /// Write to this file instead of stdout.
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Rotate the output file once it is larger than this many bytes.
+        #[arg(long, default_value_t = 10 << 20)]
+        max_bytes: u64,
+        /// Number of rotated output files to keep.
+        #[arg(long, default_value_t = 5)]
+        keep: usize,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(value) => {
+            print(&value, cli.json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e.report());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<Value> {
+    let timeout = Duration::try_from_secs_f64(cli.timeout)
+        .map_err(|e| Error::InvalidArgument(format!("timeout: {}", e)))?;
+    let u = Uhppoted::new(cli.bind, cli.broadcast, timeout).retry_policy(RetryPolicy {
+        attempts: cli.attempts,
+        ..Default::default()
+    });
+    let device = |target: &Target| u.get_device(target.device_id, cli.ip);
+
+    match &cli.command {
+        Command::Discover { range } => {
+            let mut rows = Vec::new();
+            for device in u.discover(range)? {
+                // One row per controller, with the interface it answered on as extra columns.
+                let mut row = to_value(device.config)?;
+                if let Value::Object(fields) = &mut row {
+                    fields.insert("interface".to_string(), to_value(device.interface)?);
+                    fields.insert("local_address".to_string(), to_value(device.local_address)?);
+                }
+                rows.push(row);
+            }
+            Ok(Value::Array(rows))
+        }
+        Command::GetConfig(t) => to_value(device(t).get_config()?),
+        Command::GetStatus(t) => to_value(device(t).get_status()?),
+        Command::GetTime(t) => to_value(device(t).get_time()?),
+        Command::SetTime { target, datetime } => {
+            let datetime = datetime.unwrap_or_else(|| Local::now().naive_local());
+            to_value(device(target).set_time(datetime)?)
+        }
+        Command::OpenDoor { target, door } => done(device(target).open_door(*door)),
+        Command::GetCard { target, number } => to_value(device(target).get_card_by_id(*number)?),
+        Command::PutCard {
+            target,
+            number,
+            from,
+            to,
+            doors,
+            pin,
+        } => done(device(target).add_card(Card {
+            number: *number,
+            from: *from,
+            to: *to,
+            doors: doors.clone(),
+            pin: *pin,
+        })),
+        Command::DeleteCard { target, number } => done(device(target).delete_card(*number)),
+        Command::DeleteAllCards(t) => done(device(t).clear_cards()),
+        Command::ListCards(t) => to_value(device(t).get_all_cards()?),
+        Command::GetEvent { target, index } => to_value(device(target).get_event(*index)?),
+        Command::GetEvents {
+            target,
+            after,
+            commit,
+        } => {
+            let device = device(target);
+            let mut events = match after {
+                Some(cursor) => device.events_since(*cursor)?,
+                None => device.new_events()?,
+            };
+            let entries = events.by_ref().collect::<Result<Vec<_>>>()?;
+            if *commit {
+                events.commit()?;
+            }
+            to_value(entries)
+        }
+        Command::GetListener(t) => to_value(device(t).get_listener()?),
+        Command::SetListener { target, address } => match address {
+            SocketAddr::V4(a) => done(device(target).set_listener(*a.ip(), a.port())),
+            SocketAddr::V6(_) => Err(Error::InvalidArgument(
+                "listener must be an IPv4 address".to_string(),
+            )),
+        },
+        Command::GetDoorControl { target, door } => {
+            to_value(device(target).get_door_control(*door)?)
+        }
+        Command::SetDoorControl {
+            target,
+            door,
+            mode,
+            delay,
+        } => {
+            let state = DoorControl {
+                mode: *mode,
+                delay: Duration::from_secs(*delay as u64),
+            };
+            to_value(device(target).set_door_control_state(*door, state)?)
+        }
+        Command::GetTimeProfile { target, profile_id } => {
+            to_value(device(target).get_time_profile(*profile_id)?)
+        }
+        Command::PutTimeProfile { target, profile } => {
+            done(device(target).add_or_update_time_profile(profile.clone()))
+        }
+        Command::ClearTimeProfiles(t) => done(device(t).clear_time_profiles()),
+        Command::AddTask { target, task } => done(device(target).add_task(*task)),
+        Command::ClearTasks(t) => done(device(t).clear_tasks()),
+        Command::RefreshTasks(t) => done(device(t).refresh_task_list()),
+        Command::Listen {
+            listen,
+            advertise,
+            devices,
+            output,
+            max_bytes,
+            keep,
+        } => {
+            let config = ForwarderConfig {
+                bind: *listen,
+                listener: advertise.unwrap_or(*listen),
+                devices: devices
+                    .iter()
+                    .map(|(id, ip)| (*id, ip.or(cli.ip)))
+                    .collect(),
+                output: match output {
+                    Some(path) => Output::File {
+                        path: path.clone(),
+                        max_bytes: *max_bytes,
+                        keep: *keep,
+                    },
+                    None => Output::Stdout,
+                },
+            };
+            let stop = AtomicBool::new(false);
+            done(EventForwarder::new(u, config).run(&stop))
+        }
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| Error::Decode(e.to_string()))
+}
+
+/// Result of commands that don't return anything.
+fn done(result: Result<()>) -> Result<Value> {
+    result.map(|_| Value::String("ok".to_string()))
+}
+
+/// Parse an enum by its serde name, like `normally_open`.
+fn parse_enum<T: DeserializeOwned>(s: &str) -> std::result::Result<T, String> {
+    serde_json::from_value(Value::String(s.to_string())).map_err(|e| e.to_string())
+}
+
+/// Parse a device as `ID` or `ID@IP`.
+fn parse_device(s: &str) -> std::result::Result<(u32, Option<Ipv4Addr>), String> {
+    let (id, ip) = match s.split_once('@') {
+        Some((id, ip)) => (id, Some(ip.parse().map_err(|e| format!("{}", e))?)),
+        None => (s, None),
+    };
+    Ok((id.parse().map_err(|e| format!("{}", e))?, ip))
+}
+
+/// Parse an address range as `FIRST-LAST`, or a single address.
+fn parse_range(s: &str) -> std::result::Result<RangeInclusive<Ipv4Addr>, String> {
+    let (first, last) = s.split_once('-').unwrap_or((s, s));
+    let first: Ipv4Addr = first.parse().map_err(|e| format!("{}", e))?;
+    let last: Ipv4Addr = last.parse().map_err(|e| format!("{}", e))?;
+    Ok(first..=last)
+}
+
+fn parse_json<T: DeserializeOwned>(s: &str) -> std::result::Result<T, String> {
+    serde_json::from_str(s).map_err(|e| e.to_string())
+}
+
+fn print(value: &Value, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+        return;
+    }
+    match value {
+        Value::Array(rows) => print_table(rows),
+        Value::Object(fields) => {
+            let width = fields.keys().map(|k| k.len()).max().unwrap_or(0);
+            for (key, value) in fields {
+                println!("{:width$}  {}", key, cell(value), width = width);
+            }
+        }
+        value => println!("{}", cell(value)),
+    }
+}
+
+/// Print a list of objects as a table, with the keys of the first object as columns.
+fn print_table(rows: &[Value]) {
+    let columns: Vec<String> = match rows.first() {
+        Some(Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => {
+            rows.iter().for_each(|r| println!("{}", cell(r)));
+            return;
+        }
+    };
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|c| cell(&row[c])).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| cells.iter().map(|r| r[i].len()).fold(c.len(), usize::max))
+        .collect();
+
+    let line = |values: &[String]| {
+        let padded: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .map(|(v, w)| format!("{:w$}", v, w = w))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(&columns);
+    cells.iter().for_each(|r| line(r));
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(cell).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    }
+}
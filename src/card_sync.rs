@@ -0,0 +1,192 @@
+use crate::{check_card, Card, Device, Result};
+use std::collections::BTreeMap;
+
+/// A single change needed to bring the cards on a [`Device`] in line with a desired set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum CardChange {
+    /// The card is not on the device and will be added.
+    Add(Card),
+    /// The card is on the device, but its validity dates, doors or PIN differ.
+    Update { current: Card, desired: Card },
+    /// The card is on the device, but not in the desired set and will be deleted.
+    Delete(Card),
+}
+
+impl CardChange {
+    /// The number of the card this change applies to.
+    pub fn card_number(&self) -> u32 {
+        match self {
+            CardChange::Add(card) => card.number,
+            CardChange::Update { desired, .. } => desired.number,
+            CardChange::Delete(card) => card.number,
+        }
+    }
+}
+
+/// The changes needed to sync a desired set of [`Card`]s onto a [`Device`], created with
+/// [`Device::plan_card_sync`]. Changes are ordered deletes first, then updates, then adds, so
+/// the device doesn't run out of card slots halfway through.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardSyncPlan {
+    pub changes: Vec<CardChange>,
+    /// Number of cards that are already up to date.
+    pub unchanged: usize,
+}
+
+impl CardSyncPlan {
+    /// Compute the changes that turn `current` into `desired`. When `desired` contains a card
+    /// number more than once, the last one wins.
+    pub fn new(current: Vec<Card>, desired: Vec<Card>) -> CardSyncPlan {
+        let mut current: BTreeMap<u32, Card> = current.into_iter().map(|c| (c.number, c)).collect();
+        let desired: BTreeMap<u32, Card> = desired.into_iter().map(|c| (c.number, c)).collect();
+
+        let mut updates = Vec::new();
+        let mut adds = Vec::new();
+        let mut unchanged = 0;
+        for (number, desired) in desired {
+            match current.remove(&number) {
+                None => adds.push(CardChange::Add(desired)),
+                Some(current) if current == desired => unchanged += 1,
+                Some(current) => updates.push(CardChange::Update { current, desired }),
+            }
+        }
+
+        let mut changes: Vec<CardChange> = current.into_values().map(CardChange::Delete).collect();
+        changes.append(&mut updates);
+        changes.append(&mut adds);
+        CardSyncPlan { changes, unchanged }
+    }
+
+    /// Whether the device is already in sync.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// The outcome of applying a single [`CardChange`].
+#[derive(Debug)]
+pub struct CardSyncResult {
+    pub change: CardChange,
+    pub result: Result<()>,
+}
+
+impl Device<'_> {
+    /// Compare the [`Card`]s on the [`Device`] with `desired` and return the changes needed to
+    /// make them equal, without changing anything on the device. All cards in `desired` are
+    /// validated up front.
+    pub fn plan_card_sync(&self, desired: Vec<Card>) -> Result<CardSyncPlan> {
+        desired.iter().try_for_each(check_card)?;
+        let current = self.get_all_cards()?;
+        Ok(CardSyncPlan::new(current, desired))
+    }
+
+    /// Apply a [`CardSyncPlan`]. Every change is attempted, even when earlier ones fail; the
+    /// result of each change is returned in plan order.
+    pub fn apply_card_sync(&self, plan: CardSyncPlan) -> Vec<CardSyncResult> {
+        plan.changes
+            .into_iter()
+            .map(|change| {
+                let result = match &change {
+                    CardChange::Add(card) | CardChange::Update { desired: card, .. } => {
+                        self.add_card(card.clone())
+                    }
+                    CardChange::Delete(card) => self.delete_card(card.number),
+                };
+                CardSyncResult { change, result }
+            })
+            .collect()
+    }
+
+    /// Sync `desired` onto the [`Device`]: cards that are missing are added, cards that differ
+    /// are updated and cards that are not in `desired` are deleted. Use
+    /// [`Device::plan_card_sync`] for a dry run.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use uhppote_rs::{Card, NaiveDate, Uhppoted};
+    /// let uhppoted = Uhppoted::default();
+    /// let device = uhppoted.get_device(423196779, None);
+    /// let desired = vec![Card {
+    ///     number: 8165537,
+    ///     from: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+    ///     to: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+    ///     doors: vec![1, 0, 0, 1],
+    ///     pin: None,
+    /// }];
+    /// for r in device.sync_cards(desired).unwrap() {
+    ///     if let Err(e) = r.result {
+    ///         println!("card {}: {}", r.change.card_number(), e);
+    ///     }
+    /// }
+    /// ```
+    pub fn sync_cards(&self, desired: Vec<Card>) -> Result<Vec<CardSyncResult>> {
+        let plan = self.plan_card_sync(desired)?;
+        Ok(self.apply_card_sync(plan))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn card(number: u32, doors: [u8; 4]) -> Card {
+        Card {
+            number,
+            from: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+            doors: doors.to_vec(),
+            pin: None,
+        }
+    }
+
+    #[test]
+    fn plan() {
+        let current = vec![
+            card(1, [1, 1, 1, 1]),
+            card(2, [1, 0, 0, 0]),
+            card(3, [1; 4]),
+        ];
+        let desired = vec![
+            card(2, [1, 1, 0, 0]),
+            card(3, [1; 4]),
+            card(4, [0, 0, 0, 1]),
+        ];
+        let plan = CardSyncPlan::new(current, desired);
+
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(
+            plan.changes,
+            vec![
+                CardChange::Delete(card(1, [1, 1, 1, 1])),
+                CardChange::Update {
+                    current: card(2, [1, 0, 0, 0]),
+                    desired: card(2, [1, 1, 0, 0]),
+                },
+                CardChange::Add(card(4, [0, 0, 0, 1])),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_compares_dates_and_pin() {
+        let mut later = card(1, [1; 4]);
+        later.to = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+        let plan = CardSyncPlan::new(vec![card(1, [1; 4])], vec![later]);
+        assert_eq!(plan.changes.len(), 1);
+
+        let mut pin = card(1, [1; 4]);
+        pin.pin = Some(1234);
+        let plan = CardSyncPlan::new(vec![card(1, [1; 4])], vec![pin]);
+        assert_eq!(plan.changes.len(), 1);
+    }
+
+    #[test]
+    fn plan_in_sync() {
+        let plan = CardSyncPlan::new(vec![card(1, [1; 4])], vec![card(1, [1; 4])]);
+        assert!(plan.is_empty());
+    }
+}
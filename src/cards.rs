@@ -0,0 +1,136 @@
+use crate::messages::*;
+use crate::{send_and_receive, send_and_receive_with, Card, Device, Result, RetryPolicy};
+
+/// Card number the firmware reports for a slot whose card has been deleted.
+pub(crate) const DELETED_CARD: u32 = 0xffffffff;
+
+/// Card number the firmware reports for an index past the last card.
+pub(crate) const NO_CARD: u32 = 0;
+
+/// Progress of a [`Cards`] download, passed to the callback set with [`Cards::on_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardsProgress {
+    /// The index that was just read.
+    pub index: u32,
+    /// The number of cards found so far.
+    pub found: u32,
+    /// The number of cards the [`Device`] reported when the download started.
+    pub total: u32,
+}
+
+/// Iterator over all [`Card`]s stored on a [`Device`], created with [`Device::cards`].
+///
+/// The iterator walks the card indices of the device, skipping slots of deleted cards, until it
+/// has found as many cards as the device reported or reaches the end of the card list. Requests
+/// are retried according to the [`RetryPolicy`] of the [`Uhppoted`](crate::Uhppoted), or
+/// [`Cards::retries`]; when an index can't be read, the [`Error`](crate::Error) is yielded and
+/// the iterator ends.
+///
+/// Example:
+/// ```no_run
+/// use uhppote_rs::Uhppoted;
+/// let uhppoted = Uhppoted::default();
+/// let device = uhppoted.get_device(423196779, None);
+/// let cards = device
+///     .cards()
+///     .unwrap()
+///     .on_progress(|p| println!("{}/{}", p.found, p.total));
+/// for card in cards {
+///     println!("{:?}", card);
+/// }
+/// ```
+pub struct Cards<'a> {
+    device: &'a Device<'a>,
+    index: u32,
+    found: u32,
+    total: u32,
+    retries: Option<u32>,
+    done: bool,
+    progress: Option<Box<dyn FnMut(CardsProgress) + 'a>>,
+}
+
+impl<'a> Cards<'a> {
+    pub(crate) fn new(device: &'a Device<'a>, total: u32) -> Cards<'a> {
+        Cards {
+            device,
+            index: 0,
+            found: 0,
+            total,
+            retries: None,
+            done: false,
+            progress: None,
+        }
+    }
+
+    /// Retry a timed out request up to `retries` times, instead of as often as the
+    /// [`RetryPolicy`] of the [`Uhppoted`](crate::Uhppoted) allows. The backoff of the
+    /// [`RetryPolicy`] still applies.
+    pub fn retries(mut self, retries: u32) -> Cards<'a> {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Call `progress` after every index that was read.
+    pub fn on_progress<F: FnMut(CardsProgress) + 'a>(mut self, progress: F) -> Cards<'a> {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// The number of cards the [`Device`] reported when the download started.
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    fn get(&self, index: u32) -> Result<GetCardByIndexResponse> {
+        let request = GetCardByIndexRequest::new(self.device.id, index);
+        match self.retries {
+            Some(retries) => {
+                let policy = RetryPolicy {
+                    attempts: retries + 1,
+                    ..self.device.u.retry_policy.clone()
+                };
+                send_and_receive_with(request, self.device, &policy)
+            }
+            None => send_and_receive(request, self.device),
+        }
+    }
+}
+
+impl Iterator for Cards<'_> {
+    type Item = Result<Card>;
+
+    fn next(&mut self) -> Option<Result<Card>> {
+        while !self.done && self.found < self.total {
+            self.index += 1;
+            let result = match self.get(self.index) {
+                Ok(r) if r.card_number == NO_CARD => {
+                    self.done = true;
+                    None
+                }
+                Ok(r) if r.card_number == DELETED_CARD => None,
+                Ok(r) => {
+                    self.found += 1;
+                    Some(r.try_into())
+                }
+                Err(e) => {
+                    self.done = true;
+                    Some(Err(e))
+                }
+            };
+
+            if let Some(progress) = self.progress.as_mut() {
+                progress(CardsProgress {
+                    index: self.index,
+                    found: self.found,
+                    total: self.total,
+                });
+            }
+
+            if result.is_some() {
+                return result;
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,304 @@
+use crate::dispatcher::Dispatcher;
+use crate::messages::*;
+use crate::{DeviceConfig, Error, Result, Uhppoted, UHPPOTE_PORT};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::time::Instant;
+
+/// Largest unicast range [`Uhppoted::discover`] probes, a /16.
+const MAX_RANGE: u32 = 1 << 16;
+
+/// A device found by [`Uhppoted::discover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiscoveredDevice {
+    pub config: DeviceConfig,
+    /// Name of the local interface the device answered on. For devices found in a unicast range,
+    /// the interface whose subnet contains the device, if any.
+    pub interface: Option<String>,
+    /// IPv4 address of that interface.
+    pub local_address: Option<Ipv4Addr>,
+}
+
+/// An IPv4 interface of this host.
+#[derive(Debug, Clone)]
+struct Interface {
+    name: String,
+    ip: Ipv4Addr,
+    netmask: Ipv4Addr,
+    broadcast: Option<Ipv4Addr>,
+}
+
+impl Interface {
+    fn contains(&self, ip: Ipv4Addr) -> bool {
+        let mask = u32::from(self.netmask);
+        u32::from(self.ip) & mask == u32::from(ip) & mask
+    }
+}
+
+impl Uhppoted {
+    /// Find devices on all networks this host is attached to. Unlike
+    /// [`Uhppoted::get_device_configs`], which broadcasts once from the bind address, this
+    /// broadcasts a discovery message on the directed broadcast address of every local IPv4
+    /// interface (e.g. 192.168.1.255 for 192.168.1.10/24), from that interface's address. Devices
+    /// on networks that broadcasts don't reach, such as routed subnets, can be found by listing
+    /// their addresses in `unicast`, which are probed one by one from the bind address.
+    ///
+    /// All replies that arrive within [`Uhppoted::timeout`] are merged into one
+    /// [`DiscoveredDevice`] per device ID, ordered by device ID. A device that answers on several
+    /// interfaces is reported on the first one. Interfaces and unicast addresses that fail to send
+    /// are skipped, as are replies that can't be decoded; an error is only returned when nothing
+    /// could be sent at all.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use uhppote_rs::Uhppoted;
+    /// let uhppoted = Uhppoted::default();
+    /// let range = "10.20.0.1".parse().unwrap()..="10.20.0.254".parse().unwrap();
+    /// for device in uhppoted.discover(&[range]).unwrap() {
+    ///     println!("{} on {:?}", device.config.id, device.interface);
+    /// }
+    /// ```
+    pub fn discover(&self, unicast: &[RangeInclusive<Ipv4Addr>]) -> Result<Vec<DiscoveredDevice>> {
+        let mut targets = Vec::new();
+        for range in unicast {
+            let (start, end) = (u32::from(*range.start()), u32::from(*range.end()));
+            if end.saturating_sub(start) >= MAX_RANGE {
+                return Err(Error::InvalidArgument(format!(
+                    "unicast ranges can hold at most {} addresses, got {:?}",
+                    MAX_RANGE, range
+                )));
+            }
+            targets.extend((start..=end).map(Ipv4Addr::from));
+        }
+        let interfaces = interfaces()?;
+
+        let results = std::thread::scope(|s| {
+            let broadcasts: Vec<_> = interfaces
+                .iter()
+                .filter_map(|i| Some((i, i.broadcast?)))
+                .map(|(i, broadcast)| {
+                    s.spawn(move || {
+                        let dispatcher = Dispatcher::bind(SocketAddr::new(i.ip.into(), 0))?;
+                        self.probe(&dispatcher, &[broadcast])
+                            .map(|replies| (Some(i), replies))
+                    })
+                })
+                .collect();
+            let unicast = (!targets.is_empty()).then(|| {
+                s.spawn(|| {
+                    let dispatcher = self.dispatcher()?;
+                    self.probe(&dispatcher, &targets)
+                        .map(|replies| (None, replies))
+                })
+            });
+            broadcasts
+                .into_iter()
+                .chain(unicast)
+                .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect::<Vec<_>>()
+        });
+
+        self.merge(results, &interfaces)
+    }
+
+    /// Merge the replies of all probes, with the interface they were received on (`None` for
+    /// unicast), into one [`DiscoveredDevice`] per device ID.
+    fn merge(
+        &self,
+        results: Vec<Result<(Option<&Interface>, Vec<GetConfigResponse>)>>,
+        interfaces: &[Interface],
+    ) -> Result<Vec<DiscoveredDevice>> {
+        let mut devices = BTreeMap::new();
+        let mut error = None;
+        let mut sent = false;
+        for result in results {
+            let (interface, replies) = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    continue;
+                }
+            };
+            sent = true;
+            for reply in replies {
+                let message_type = reply.message_type;
+                let config = match DeviceConfig::try_from(reply) {
+                    Ok(config) => config,
+                    Err(_) => {
+                        self.metrics.record(message_type, |m| m.decode_errors += 1);
+                        continue;
+                    }
+                };
+                let interface =
+                    interface.or_else(|| interfaces.iter().find(|i| i.contains(config.address)));
+                devices
+                    .entry(config.id)
+                    .or_insert_with(|| DiscoveredDevice {
+                        interface: interface.map(|i| i.name.clone()),
+                        local_address: interface.map(|i| i.ip),
+                        config,
+                    });
+            }
+        }
+        match error {
+            Some(e) if !sent => Err(e),
+            _ => Ok(devices.into_values().collect()),
+        }
+    }
+
+    /// Send a discovery message to port 60000 of every address in `targets` and collect the
+    /// replies that arrive within [`Uhppoted::timeout`]. Fails only when no message could be sent.
+    fn probe(
+        &self,
+        dispatcher: &Dispatcher,
+        targets: &[Ipv4Addr],
+    ) -> Result<Vec<GetConfigResponse>> {
+        let request = GetConfigRequest::new(0);
+        let message_type = Request::get_message_type(&request);
+        let replies = dispatcher.subscribe(0, message_type, self.timeout)?;
+        let bytes = request.to_bytes();
+        let mut error = None;
+        let mut sent = false;
+        for target in targets {
+            // An unreachable address is no reason not to probe the others.
+            match dispatcher.send_to(&bytes, SocketAddr::new(IpAddr::V4(*target), UHPPOTE_PORT)) {
+                Ok(()) => {
+                    sent = true;
+                    self.metrics.record(message_type, |m| m.requests += 1);
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        if let (false, Some(e)) = (sent, error) {
+            return Err(e);
+        }
+
+        let mut ret = Vec::new();
+        let deadline = Instant::now() + self.timeout;
+        while let Some(datagram) =
+            replies.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            match decode_reply(&request, &datagram) {
+                Ok(response) => ret.extend(response),
+                Err(_) => self.metrics.record(message_type, |m| m.decode_errors += 1),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// The IPv4 interfaces of this host.
+fn interfaces() -> Result<Vec<Interface>> {
+    Ok(if_addrs::get_if_addrs()?
+        .into_iter()
+        .filter_map(|i| match i.addr {
+            if_addrs::IfAddr::V4(addr) => Some(Interface {
+                name: i.name,
+                ip: addr.ip,
+                netmask: addr.netmask,
+                broadcast: addr.broadcast,
+            }),
+            if_addrs::IfAddr::V6(_) => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interface_contains_its_subnet() {
+        let interface = Interface {
+            name: "eth0".to_string(),
+            ip: Ipv4Addr::new(192, 168, 1, 10),
+            netmask: Ipv4Addr::new(255, 255, 255, 0),
+            broadcast: Some(Ipv4Addr::new(192, 168, 1, 255)),
+        };
+        assert!(interface.contains(Ipv4Addr::new(192, 168, 1, 200)));
+        assert!(!interface.contains(Ipv4Addr::new(192, 168, 2, 200)));
+    }
+
+    fn interface(name: &str, ip: [u8; 4], netmask: [u8; 4]) -> Interface {
+        Interface {
+            name: name.to_string(),
+            ip: ip.into(),
+            netmask: netmask.into(),
+            broadcast: None,
+        }
+    }
+
+    fn reply_bytes(device_id: u32, ip: [u8; 4]) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..4].copy_from_slice(&[0x17, 0x94, 0x00, 0x00]);
+        bytes[4..8].copy_from_slice(&device_id.to_le_bytes());
+        bytes[8..12].copy_from_slice(&ip);
+        bytes[26..32].copy_from_slice(&[0x08, 0x92, 0x20, 0x18, 0x08, 0x16]);
+        bytes
+    }
+
+    fn reply(device_id: u32, ip: [u8; 4]) -> GetConfigResponse {
+        GetConfigResponse::from_bytes(&reply_bytes(device_id, ip)).unwrap()
+    }
+
+    #[test]
+    fn replies_are_merged_per_device() {
+        let u = Uhppoted::default();
+        let interfaces = [
+            interface("eth0", [192, 168, 1, 10], [255, 255, 255, 0]),
+            interface("eth1", [10, 0, 0, 5], [255, 0, 0, 0]),
+        ];
+        // A reply whose date is not BCD.
+        let mut bytes = reply_bytes(4, [192, 168, 1, 104]);
+        bytes[28..32].copy_from_slice(&[0xff; 4]);
+        let undecodable = GetConfigResponse::from_bytes(&bytes).unwrap();
+
+        let results = vec![
+            Ok((
+                Some(&interfaces[0]),
+                vec![reply(1, [192, 168, 1, 101]), undecodable],
+            )),
+            Err(Error::Timeout),
+            Ok((
+                None,
+                vec![
+                    reply(1, [192, 168, 1, 101]),
+                    reply(2, [10, 20, 0, 7]),
+                    reply(3, [172, 16, 0, 1]),
+                ],
+            )),
+        ];
+        let devices = u.merge(results, &interfaces).unwrap();
+        let found: Vec<_> = devices
+            .iter()
+            .map(|d| (d.config.id, d.interface.as_deref(), d.local_address))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, Some("eth0"), Some(Ipv4Addr::new(192, 168, 1, 10))),
+                (2, Some("eth1"), Some(Ipv4Addr::new(10, 0, 0, 5))),
+                (3, None, None),
+            ]
+        );
+        assert_eq!(u.request_metrics()["GetConfig"].decode_errors, 1);
+
+        assert!(matches!(
+            u.merge(vec![Err(Error::Timeout)], &interfaces),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn large_ranges_are_rejected() {
+        let range = Ipv4Addr::new(10, 0, 0, 0)..=Ipv4Addr::new(10, 1, 0, 0);
+        assert!(matches!(
+            Uhppoted::default().discover(&[range]),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{Error, Result};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often the receive loop checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A UDP socket shared by all requests of an [`Uhppoted`](crate::Uhppoted). A background thread
+/// receives all replies and hands each one to the caller waiting for it, so any number of
+/// requests can be in flight at the same time.
+///
+/// Replies are matched to callers by device ID and message type, since that is all a reply
+/// carries. Two requests of the same type to the same device can't be told apart, so only one
+/// caller at a time waits for a specific device and message type; others wait for their turn
+/// before sending. A caller receives replies until it drops its [`Subscription`]. Callers waiting
+/// for device ID 0 receive the replies of all devices.
+///
+/// With the `tokio` feature, [`Dispatcher::subscribe_async`] waits for replies without blocking
+/// a thread of the runtime.
+#[derive(Debug)]
+pub(crate) struct Dispatcher {
+    shared: Arc<Shared>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    socket: UdpSocket,
+    waiters: Mutex<Vec<Waiter>>,
+    /// Signalled whenever a [`Subscription`] is dropped, so the next caller can take its turn.
+    turns: Condvar,
+    /// Like `turns`, for callers of [`Dispatcher::subscribe_async`].
+    #[cfg(feature = "tokio")]
+    async_turns: tokio::sync::Notify,
+    next_id: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    device_id: u32,
+    message_type: u8,
+    replies: Replies,
+}
+
+/// Where the replies for a [`Waiter`] go.
+#[derive(Debug)]
+enum Replies {
+    Blocking(Sender<Vec<u8>>),
+    #[cfg(feature = "tokio")]
+    Async(tokio::sync::mpsc::UnboundedSender<Vec<u8>>),
+}
+
+/// The place of a caller among the waiters. Dropping it stops the delivery of replies and lets
+/// the next caller for the same device and message type take its turn.
+struct Registration {
+    shared: Arc<Shared>,
+    id: u64,
+}
+
+/// Replies for a single caller, created with [`Dispatcher::subscribe`]. Dropping it stops the
+/// delivery of replies.
+pub(crate) struct Subscription {
+    _registration: Registration,
+    replies: Receiver<Vec<u8>>,
+}
+
+/// Replies for a single caller, created with [`Dispatcher::subscribe_async`]. Dropping it stops
+/// the delivery of replies.
+#[cfg(feature = "tokio")]
+pub(crate) struct AsyncSubscription {
+    _registration: Registration,
+    replies: tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>,
+}
+
+impl Dispatcher {
+    /// Bind the shared socket to `address` and start the receive loop.
+    pub(crate) fn bind(address: SocketAddr) -> Result<Dispatcher> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_write_timeout(Some(Duration::new(1, 0)))?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        socket.set_broadcast(true)?;
+        let shared = Arc::new(Shared {
+            socket,
+            waiters: Mutex::new(Vec::new()),
+            turns: Condvar::new(),
+            #[cfg(feature = "tokio")]
+            async_turns: tokio::sync::Notify::new(),
+            next_id: AtomicU64::new(0),
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let shared = shared.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || receive(&shared, &stop))
+        };
+
+        Ok(Dispatcher {
+            shared,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Start collecting replies of `message_type` from `device_id` (0 for all devices). Subscribe
+    /// before sending the request, so the reply can't arrive before anyone waits for it.
+    ///
+    /// While another caller waits for the same device and message type, this waits up to
+    /// `timeout` for it to finish and fails with [`Error::Timeout`] if it doesn't.
+    pub(crate) fn subscribe(
+        &self,
+        device_id: u32,
+        message_type: u8,
+        timeout: Duration,
+    ) -> Result<Subscription> {
+        let deadline = Instant::now() + timeout;
+        let mut waiters = self.shared.waiters();
+        while is_taken(&waiters, device_id, message_type) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::Timeout);
+            }
+            waiters = self
+                .shared
+                .turns
+                .wait_timeout(waiters, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+
+        let (tx, replies) = mpsc::channel();
+        Ok(Subscription {
+            _registration: self.register(
+                &mut waiters,
+                device_id,
+                message_type,
+                Replies::Blocking(tx),
+            ),
+            replies,
+        })
+    }
+
+    /// Like [`Dispatcher::subscribe`], but waits for the turn of the caller without blocking.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn subscribe_async(
+        &self,
+        device_id: u32,
+        message_type: u8,
+        timeout: Duration,
+    ) -> Result<AsyncSubscription> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Listen for the end of a turn before checking, so it can't end unnoticed in between.
+            let turn = self.shared.async_turns.notified();
+            tokio::pin!(turn);
+            turn.as_mut().enable();
+            {
+                let mut waiters = self.shared.waiters();
+                if !is_taken(&waiters, device_id, message_type) {
+                    let (tx, replies) = tokio::sync::mpsc::unbounded_channel();
+                    return Ok(AsyncSubscription {
+                        _registration: self.register(
+                            &mut waiters,
+                            device_id,
+                            message_type,
+                            Replies::Async(tx),
+                        ),
+                        replies,
+                    });
+                }
+            }
+            if tokio::time::timeout_at(deadline, turn).await.is_err() {
+                return Err(Error::Timeout);
+            }
+        }
+    }
+
+    fn register(
+        &self,
+        waiters: &mut Vec<Waiter>,
+        device_id: u32,
+        message_type: u8,
+        replies: Replies,
+    ) -> Registration {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        waiters.push(Waiter {
+            id,
+            device_id,
+            message_type,
+            replies,
+        });
+        Registration {
+            shared: self.shared.clone(),
+            id,
+        }
+    }
+
+    pub(crate) fn send_to(&self, datagram: &[u8], address: SocketAddr) -> Result<()> {
+        self.shared.socket.send_to(datagram, address)?;
+        Ok(())
+    }
+}
+
+impl Drop for Dispatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Subscription {
+    /// Wait up to `timeout` for the next reply. Returns `None` when none arrived in time.
+    pub(crate) fn recv_timeout(&self, timeout: Duration) -> Option<Vec<u8>> {
+        self.replies.recv_timeout(timeout).ok()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncSubscription {
+    /// Wait for the next reply.
+    pub(crate) async fn recv(&mut self) -> Option<Vec<u8>> {
+        self.replies.recv().await
+    }
+}
+
+impl Replies {
+    fn send(&self, datagram: Vec<u8>) {
+        // The caller may have stopped waiting in the meantime.
+        let _ = match self {
+            Replies::Blocking(tx) => tx.send(datagram).is_ok(),
+            #[cfg(feature = "tokio")]
+            Replies::Async(tx) => tx.send(datagram).is_ok(),
+        };
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.shared.waiters().retain(|w| w.id != self.id);
+        self.shared.turns.notify_all();
+        #[cfg(feature = "tokio")]
+        self.shared.async_turns.notify_waiters();
+    }
+}
+
+impl Shared {
+    fn waiters(&self) -> std::sync::MutexGuard<'_, Vec<Waiter>> {
+        self.waiters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Hand `datagram` to the caller waiting for its device and to all callers waiting for any
+    /// device.
+    fn dispatch(&self, datagram: &[u8]) {
+        // Every message starts with a header byte, the message type, two unused bytes and the
+        // device ID. Anything shorter can't be routed.
+        if datagram.len() < 8 {
+            return;
+        }
+        let message_type = datagram[1];
+        let device_id = u32::from_le_bytes([datagram[4], datagram[5], datagram[6], datagram[7]]);
+
+        let waiters = self.waiters();
+        for waiter in waiters.iter().filter(|w| {
+            w.message_type == message_type && (w.device_id == device_id || w.device_id == 0)
+        }) {
+            waiter.replies.send(datagram.to_vec());
+        }
+    }
+}
+
+/// Whether a caller waits for `message_type` from `device_id`, so others have to wait for their
+/// turn.
+fn is_taken(waiters: &[Waiter], device_id: u32, message_type: u8) -> bool {
+    device_id != 0
+        && waiters
+            .iter()
+            .any(|w| w.device_id == device_id && w.message_type == message_type)
+}
+
+/// Receive datagrams and dispatch them until `stop` is set.
+fn receive(shared: &Shared, stop: &AtomicBool) {
+    let mut buf = [0u8; 1024];
+    while !stop.load(Ordering::Relaxed) {
+        if let Ok((n, _)) = shared.socket.recv_from(&mut buf) {
+            shared.dispatch(&buf[..n]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(device_id: u32, message_type: u8) -> Vec<u8> {
+        let mut datagram = vec![0x17, message_type, 0, 0];
+        datagram.extend_from_slice(&device_id.to_le_bytes());
+        datagram.resize(64, 0);
+        datagram
+    }
+
+    #[test]
+    fn replies_are_routed_to_the_waiting_caller() {
+        let dispatcher = Dispatcher::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let timeout = Duration::from_millis(50);
+        let status_1 = dispatcher.subscribe(1, 0x20, timeout).unwrap();
+        let status_2 = dispatcher.subscribe(2, 0x20, timeout).unwrap();
+        let time_1 = dispatcher.subscribe(1, 0x32, timeout).unwrap();
+        let discovery = dispatcher.subscribe(0, 0x20, timeout).unwrap();
+        let discovery_again = dispatcher.subscribe(0, 0x20, timeout).unwrap();
+
+        dispatcher.shared.dispatch(&reply(1, 0x20));
+        assert_eq!(status_1.recv_timeout(timeout), Some(reply(1, 0x20)));
+        assert_eq!(status_2.recv_timeout(timeout), None);
+        assert_eq!(time_1.recv_timeout(timeout), None);
+        assert_eq!(discovery.recv_timeout(timeout), Some(reply(1, 0x20)));
+        assert_eq!(discovery_again.recv_timeout(timeout), Some(reply(1, 0x20)));
+
+        // Callers keep receiving until they stop waiting.
+        dispatcher.shared.dispatch(&reply(1, 0x20));
+        assert_eq!(status_1.recv_timeout(timeout), Some(reply(1, 0x20)));
+    }
+
+    #[test]
+    fn callers_for_the_same_device_take_turns() {
+        let dispatcher = Arc::new(Dispatcher::bind("127.0.0.1:0".parse().unwrap()).unwrap());
+        let first = dispatcher.subscribe(1, 0x20, Duration::ZERO).unwrap();
+        assert!(matches!(
+            dispatcher.subscribe(1, 0x20, Duration::from_millis(50)),
+            Err(Error::Timeout)
+        ));
+
+        let second = {
+            let dispatcher = dispatcher.clone();
+            std::thread::spawn(move || {
+                let second = dispatcher
+                    .subscribe(1, 0x20, Duration::from_secs(5))
+                    .unwrap();
+                second.recv_timeout(Duration::from_secs(5))
+            })
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        dispatcher.shared.dispatch(&reply(1, 0x20));
+        assert_eq!(
+            first.recv_timeout(Duration::from_millis(50)),
+            Some(reply(1, 0x20))
+        );
+        drop(first);
+
+        std::thread::sleep(Duration::from_millis(50));
+        dispatcher.shared.dispatch(&reply(1, 0x20));
+        assert_eq!(second.join().unwrap(), Some(reply(1, 0x20)));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_callers_take_turns_without_blocking() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let dispatcher = Arc::new(Dispatcher::bind("127.0.0.1:0".parse().unwrap()).unwrap());
+        runtime.block_on(async {
+            let first = dispatcher
+                .subscribe_async(1, 0x20, Duration::ZERO)
+                .await
+                .unwrap();
+            assert!(matches!(
+                dispatcher
+                    .subscribe_async(1, 0x20, Duration::from_millis(50))
+                    .await,
+                Err(Error::Timeout)
+            ));
+
+            // The runtime has a single thread, so this only gets its turn if waiting for it
+            // doesn't block.
+            let second = {
+                let dispatcher = dispatcher.clone();
+                tokio::spawn(async move {
+                    let mut second = dispatcher
+                        .subscribe_async(1, 0x20, Duration::from_secs(5))
+                        .await
+                        .unwrap();
+                    second.recv().await
+                })
+            };
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(first);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            dispatcher.shared.dispatch(&reply(1, 0x20));
+            assert_eq!(second.await.unwrap(), Some(reply(1, 0x20)));
+        });
+    }
+}
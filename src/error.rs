@@ -0,0 +1,137 @@
+use std::io;
+
+/// Errors that can occur while talking to a UHPPOTE [`Device`](crate::Device).
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// No response was received within the configured timeout.
+    #[error("timed out waiting for a response")]
+    Timeout,
+
+    /// An I/O error occurred while sending or receiving a message.
+    #[error("I/O error")]
+    Io(#[source] io::Error),
+
+    /// The device answered, but reported that the operation did not succeed.
+    #[error("{operation} was rejected by the device")]
+    Rejected { operation: &'static str },
+
+    /// A received message could not be decoded.
+    #[error("unable to decode message: {0}")]
+    Decode(String),
+
+    /// A BCD encoded date or time could not be converted.
+    #[error("invalid BCD value: {0}")]
+    InvalidBcd(String),
+
+    /// A message of an unexpected or unknown type was received.
+    #[error("unexpected message type: {0:#04x}")]
+    UnexpectedMessageType(u8),
+
+    /// A response was received from a different device than the one addressed.
+    #[error("expected a response from device {expected}, got one from {actual}")]
+    DeviceIdMismatch { expected: u32, actual: u32 },
+
+    /// An argument passed to an operation is out of range or otherwise invalid.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// A request still failed after it was sent `attempts` times, see
+    /// [`RetryPolicy`](crate::RetryPolicy). `error` is the error of the last attempt.
+    #[error("gave up after {attempts} attempts")]
+    Retried {
+        attempts: u32,
+        #[source]
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// The underlying error: the error of the last attempt for [`Error::Retried`], the error
+    /// itself otherwise.
+    pub fn cause(&self) -> &Error {
+        match self {
+            Error::Retried { error, .. } => error.cause(),
+            e => e,
+        }
+    }
+
+    /// This error followed by its sources, separated by `: `, for instance `gave up after 3
+    /// attempts: timed out waiting for a response`.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(e) = source {
+            report.push_str(": ");
+            report.push_str(&e.to_string());
+            source = e.source();
+        }
+        report
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            // A read timeout on a std socket surfaces as WouldBlock on Unix and TimedOut on Windows.
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Error::Timeout,
+            _ => Error::Io(e),
+        }
+    }
+}
+
+impl From<bincode::error::DecodeError> for Error {
+    fn from(e: bincode::error::DecodeError) -> Self {
+        Error::Decode(e.to_string())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<tokio::time::error::Elapsed> for Error {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
+        Error::Timeout
+    }
+}
+
+/// Result type used throughout this crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_timeouts_map_to_timeout() {
+        let e: Error = io::Error::from(io::ErrorKind::WouldBlock).into();
+        assert!(matches!(e, Error::Timeout));
+        let e: Error = io::Error::from(io::ErrorKind::TimedOut).into();
+        assert!(matches!(e, Error::Timeout));
+        let e: Error = io::Error::from(io::ErrorKind::AddrInUse).into();
+        assert!(matches!(e, Error::Io(_)));
+    }
+
+    #[test]
+    fn source_is_kept() {
+        use std::error::Error as _;
+
+        let e: Error = io::Error::from(io::ErrorKind::AddrInUse).into();
+        let source = e.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::AddrInUse);
+
+        let e = Error::Retried {
+            attempts: 3,
+            error: Box::new(e),
+        };
+        let source = e.source().unwrap();
+        assert_eq!(source.to_string(), "I/O error");
+        assert!(source.source().unwrap().is::<io::Error>());
+
+        // Each error in the chain is only printed once.
+        assert_eq!(
+            e.report(),
+            format!(
+                "gave up after 3 attempts: I/O error: {}",
+                io::Error::from(io::ErrorKind::AddrInUse)
+            )
+        );
+    }
+}
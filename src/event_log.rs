@@ -0,0 +1,218 @@
+use crate::messages::*;
+use crate::{send_and_receive, Device, Event, Result};
+
+/// Index of the first event that is still stored on the device.
+const FIRST_EVENT: u32 = 0;
+
+/// Index of the last event stored on the device.
+const LAST_EVENT: u32 = 0xffffffff;
+
+/// Event type the firmware reports for an event that has been overwritten.
+const OVERWRITTEN: u8 = 0xff;
+
+/// An entry of the event log of a [`Device`], yielded by [`Events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum EventLogEntry {
+    /// An [`Event`] that was read successfully.
+    Event(Event),
+    /// Events `from..=to` were overwritten, because the event log of the device wrapped around.
+    Overwritten { from: u32, to: u32 },
+    /// Events `from..=to` could not be found on the device.
+    Missing { from: u32, to: u32 },
+}
+
+impl EventLogEntry {
+    /// The highest event index covered by this entry.
+    pub fn last_index(&self) -> u32 {
+        match self {
+            EventLogEntry::Event(event) => event.index,
+            EventLogEntry::Overwritten { to, .. } | EventLogEntry::Missing { to, .. } => *to,
+        }
+    }
+}
+
+/// Iterator over the events of a [`Device`] after a cursor, created with
+/// [`Device::events_since`] or [`Device::new_events`].
+///
+/// Entries are yielded in order up to the last event that was stored when the iterator was
+/// created. Ranges of events that were overwritten or are missing are yielded as a single
+/// entry. [`Events::cursor`] is the index of the last entry yielded and can be stored to resume
+/// later. When a request fails, the [`Error`](crate::Error) is yielded and the iterator ends, so
+/// the cursor still points at the last entry that was fetched.
+///
+/// Example:
+/// ```no_run
+/// use uhppote_rs::{EventLogEntry, Uhppoted};
+/// let uhppoted = Uhppoted::default();
+/// let device = uhppoted.get_device(423196779, None);
+/// let mut events = device.new_events().unwrap();
+/// for entry in events.by_ref() {
+///     match entry.unwrap() {
+///         EventLogEntry::Event(event) => println!("{:?}", event),
+///         gap => println!("lost events: {:?}", gap),
+///     }
+/// }
+/// // Only move the device's event index once the events have been stored.
+/// events.commit().unwrap();
+/// ```
+pub struct Events<'a> {
+    device: &'a Device<'a>,
+    next: u32,
+    last: u32,
+    cursor: u32,
+    done: bool,
+    gap: Option<EventLogEntry>,
+    queued: Option<Result<EventLogEntry>>,
+}
+
+/// What was found at a single index of the event log.
+enum Slot {
+    Event(Event),
+    Overwritten { from: u32, to: u32 },
+    Missing(u32),
+}
+
+impl<'a> Events<'a> {
+    /// Create an iterator over the events after `cursor`. When `cursor` is past the last event,
+    /// for instance because the event log of the device was cleared, it starts at the first
+    /// event.
+    pub(crate) fn new(device: &'a Device<'a>, cursor: u32) -> Result<Events<'a>> {
+        let last = get_event(device, LAST_EVENT)?.index;
+        let cursor = if cursor > last { 0 } else { cursor };
+        Ok(Events {
+            device,
+            next: cursor + 1,
+            last,
+            cursor,
+            done: false,
+            gap: None,
+            queued: None,
+        })
+    }
+
+    /// The index of the last entry that was yielded. Store this to resume with
+    /// [`Device::events_since`].
+    pub fn cursor(&self) -> u32 {
+        self.cursor
+    }
+
+    /// The index of the last event stored on the [`Device`] when the iterator was created.
+    pub fn end_index(&self) -> u32 {
+        self.last
+    }
+
+    /// Set the event index of the [`Device`] to [`Events::cursor`], so [`Device::new_events`]
+    /// continues from there. Call this after the yielded events have been persisted.
+    pub fn commit(&self) -> Result<()> {
+        self.device.set_event_index(self.cursor)
+    }
+
+    fn fetch(&self, index: u32) -> Result<Slot> {
+        let response = get_event(self.device, index)?;
+        if response.index != index {
+            return Ok(Slot::Missing(index));
+        }
+        if response.type_ != OVERWRITTEN {
+            return Ok(Slot::Event(response.try_into()?));
+        }
+        // Skip ahead to the oldest event that is still stored, rather than fetching each of the
+        // overwritten events.
+        let first = get_event(self.device, FIRST_EVENT)?.index;
+        let to = if first > index { first - 1 } else { index };
+        Ok(Slot::Overwritten {
+            from: index,
+            to: to.min(self.last),
+        })
+    }
+
+    /// Merge `entry` into the pending gap. Returns the previous gap if it can't be merged.
+    fn extend_gap(&mut self, entry: EventLogEntry) -> Option<EventLogEntry> {
+        match (self.gap.as_mut(), &entry) {
+            (
+                Some(EventLogEntry::Overwritten { to, .. }),
+                EventLogEntry::Overwritten { to: end, .. },
+            )
+            | (Some(EventLogEntry::Missing { to, .. }), EventLogEntry::Missing { to: end, .. }) => {
+                *to = *end;
+                None
+            }
+            _ => self.gap.replace(entry),
+        }
+    }
+
+    fn advance(&mut self) -> Option<Result<EventLogEntry>> {
+        if let Some(item) = self.queued.take() {
+            return Some(item);
+        }
+        while !self.done && self.next <= self.last {
+            let index = self.next;
+            let entry = match self.fetch(index) {
+                Ok(Slot::Event(event)) => Ok(EventLogEntry::Event(event)),
+                Ok(Slot::Overwritten { from, to }) => {
+                    self.next = to + 1;
+                    match self.extend_gap(EventLogEntry::Overwritten { from, to }) {
+                        Some(gap) => return Some(Ok(gap)),
+                        None => continue,
+                    }
+                }
+                Ok(Slot::Missing(index)) => {
+                    self.next = index + 1;
+                    let gap = EventLogEntry::Missing {
+                        from: index,
+                        to: index,
+                    };
+                    match self.extend_gap(gap) {
+                        Some(gap) => return Some(Ok(gap)),
+                        None => continue,
+                    }
+                }
+                Err(e) => {
+                    self.done = true;
+                    Err(e)
+                }
+            };
+            self.next = index + 1;
+            return match self.gap.take() {
+                Some(gap) => {
+                    self.queued = Some(entry);
+                    Some(Ok(gap))
+                }
+                None => Some(entry),
+            };
+        }
+        self.gap.take().map(Ok)
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<EventLogEntry>;
+
+    fn next(&mut self) -> Option<Result<EventLogEntry>> {
+        let item = self.advance();
+        if let Some(Ok(entry)) = &item {
+            self.cursor = entry.last_index();
+        }
+        item
+    }
+}
+
+fn get_event(device: &Device, index: u32) -> Result<GetEventResponse> {
+    let request = GetEventRequest::new(device.id, index);
+    send_and_receive(request, device)
+}
+
+impl Device<'_> {
+    /// Iterate over the events of the [`Device`] after `cursor`. See [`Events`] for details.
+    pub fn events_since(&self, cursor: u32) -> Result<Events<'_>> {
+        Events::new(self, cursor)
+    }
+
+    /// Iterate over the events of the [`Device`] after its event index (see
+    /// [`Device::get_event_index`]). Use [`Events::commit`] to move the event index forward.
+    pub fn new_events(&self) -> Result<Events<'_>> {
+        let cursor = self.get_event_index()?;
+        Events::new(self, cursor)
+    }
+}
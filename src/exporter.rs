@@ -0,0 +1,269 @@
+//! An HTTP server exposing the health of devices as Prometheus metrics.
+use crate::{Error, RequestMetrics, Result, Status, Uhppoted};
+use chrono::Local;
+use std::fmt::Write;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+/// How often [`MetricsExporter::run`] checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An HTTP server that serves metrics of devices in the Prometheus text format on `/metrics`.
+///
+/// Devices are polled with [`Device::get_status`](crate::Device::get_status),
+/// [`Device::get_time`](crate::Device::get_time) and
+/// [`Device::get_event_index`](crate::Device::get_event_index) on every scrape, so the scrape
+/// timeout of Prometheus should allow for [`Uhppoted`]'s timeout for each device that doesn't
+/// respond. Per device, labeled with `device_id`:
+///
+/// | Metric | |
+/// |--------|-|
+/// | `uhppote_up` | 1 if the device responded, 0 otherwise |
+/// | `uhppote_door_open` | 1 if the door (labeled `door`) is open |
+/// | `uhppote_door_button_pressed` | 1 if the button of the door (labeled `door`) is pressed |
+/// | `uhppote_relay_state`, `uhppote_input_state` | relay and input bitmaps |
+/// | `uhppote_system_error`, `uhppote_special_info` | as reported by the device |
+/// | `uhppote_last_event_index` | index of the last event, use `rate()` for the event rate |
+/// | `uhppote_event_index` | see [`Device::get_event_index`](crate::Device::get_event_index) |
+/// | `uhppote_clock_drift_seconds` | time of the device minus local time |
+///
+/// And for every message type sent (labeled `type`), from [`Uhppoted::request_metrics`]:
+/// `uhppote_client_requests_total`, `uhppote_client_timeouts_total`,
+/// `uhppote_client_decode_errors_total` and the `uhppote_client_request_duration_seconds` summary.
+///
+/// Example:
+/// ```no_run
+/// use std::sync::atomic::AtomicBool;
+/// use uhppote_rs::{MetricsExporter, Uhppoted};
+/// let exporter = MetricsExporter::bind(
+///     Uhppoted::default(),
+///     vec![(423196779, Some("192.168.1.100".parse().unwrap()))],
+///     "0.0.0.0:9100".parse().unwrap(),
+/// )
+/// .unwrap();
+/// exporter.run(&AtomicBool::new(false)).unwrap();
+/// ```
+pub struct MetricsExporter {
+    uhppoted: Uhppoted,
+    devices: Vec<(u32, Option<Ipv4Addr>)>,
+    server: Server,
+}
+
+/// Name, help text and value of a metric derived from `T`.
+type Metric<T, V> = (&'static str, &'static str, fn(&T) -> V);
+
+/// What was read from a device during a scrape.
+struct Sample {
+    status: Status,
+    event_index: u32,
+    /// Time of the device minus local time, in seconds.
+    clock_drift: f64,
+}
+
+impl MetricsExporter {
+    /// Create a [`MetricsExporter`] listening on `address`, exporting metrics of `devices`. A
+    /// [`Controller`](crate::Controller) registered with
+    /// [`Uhppoted::controllers`] for one of `devices` takes precedence over its address.
+    pub fn bind(
+        uhppoted: Uhppoted,
+        devices: Vec<(u32, Option<Ipv4Addr>)>,
+        address: SocketAddr,
+    ) -> Result<MetricsExporter> {
+        let server =
+            Server::http(address).map_err(|e| Error::Io(io::Error::other(e.to_string())))?;
+        Ok(MetricsExporter {
+            uhppoted,
+            devices,
+            server,
+        })
+    }
+
+    /// The address the [`MetricsExporter`] is listening on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Serve requests until `stop` is set.
+    pub fn run(&self, stop: &AtomicBool) -> Result<()> {
+        let content_type = Header::from_bytes(
+            &b"Content-Type"[..],
+            &b"text/plain; version=0.0.4; charset=utf-8"[..],
+        )
+        .expect("valid header");
+        while !stop.load(Ordering::Relaxed) {
+            let request = match self.server.recv_timeout(POLL_INTERVAL)? {
+                Some(request) => request,
+                None => continue,
+            };
+            let response = match request.url().split('?').next() {
+                Some("/metrics") => {
+                    Response::from_string(self.render()).with_header(content_type.clone())
+                }
+                _ => Response::from_string("not found\n").with_status_code(404),
+            };
+            // The client may have gone away; that's no reason to stop serving others.
+            let _ = request.respond(response);
+        }
+        Ok(())
+    }
+
+    fn sample(&self, id: u32, address: Option<Ipv4Addr>) -> Result<Sample> {
+        let device = self.uhppoted.listed_device(id, address);
+        let status = device.get_status()?;
+        let clock_drift = device.get_time()? - Local::now().naive_local();
+        Ok(Sample {
+            status,
+            event_index: device.get_event_index()?,
+            clock_drift: clock_drift.num_milliseconds() as f64 / 1000.0,
+        })
+    }
+
+    /// Poll all devices and render their metrics and the client metrics.
+    fn render(&self) -> String {
+        let samples: Vec<(u32, Option<Sample>)> = self
+            .devices
+            .iter()
+            .map(|(id, address)| (*id, self.sample(*id, *address).ok()))
+            .collect();
+
+        let mut out = String::new();
+        family(
+            &mut out,
+            "uhppote_up",
+            "gauge",
+            "Whether the device responded.",
+        );
+        for (id, sample) in &samples {
+            sample_line(
+                &mut out,
+                "uhppote_up",
+                &device_labels(*id),
+                sample.is_some() as u8,
+            );
+        }
+
+        let samples: Vec<(u32, Sample)> = samples
+            .into_iter()
+            .filter_map(|(id, sample)| Some((id, sample?)))
+            .collect();
+
+        family(
+            &mut out,
+            "uhppote_door_open",
+            "gauge",
+            "Whether the door is open.",
+        );
+        for (id, sample) in &samples {
+            for (door, open) in sample.status.doors.iter().enumerate() {
+                let labels = format!("device_id=\"{}\",door=\"{}\"", id, door + 1);
+                sample_line(&mut out, "uhppote_door_open", &labels, *open as u8);
+            }
+        }
+        family(
+            &mut out,
+            "uhppote_door_button_pressed",
+            "gauge",
+            "Whether the button of the door is pressed.",
+        );
+        for (id, sample) in &samples {
+            for (door, pressed) in sample.status.buttons.iter().enumerate() {
+                let labels = format!("device_id=\"{}\",door=\"{}\"", id, door + 1);
+                sample_line(
+                    &mut out,
+                    "uhppote_door_button_pressed",
+                    &labels,
+                    *pressed as u8,
+                );
+            }
+        }
+
+        let gauges: [Metric<Sample, f64>; 7] = [
+            ("uhppote_relay_state", "Bitmap of the relay states.", |s| {
+                s.status.relay_state.into()
+            }),
+            ("uhppote_input_state", "Bitmap of the input states.", |s| {
+                s.status.input_state.into()
+            }),
+            ("uhppote_system_error", "System error code.", |s| {
+                s.status.system_error.into()
+            }),
+            ("uhppote_special_info", "Special info code.", |s| {
+                s.status.special_info.into()
+            }),
+            (
+                "uhppote_last_event_index",
+                "Index of the last event.",
+                |s| s.status.last_event.as_ref().map_or(0, |e| e.index).into(),
+            ),
+            ("uhppote_event_index", "Event index of the device.", |s| {
+                s.event_index.into()
+            }),
+            (
+                "uhppote_clock_drift_seconds",
+                "Time of the device minus local time.",
+                |s| s.clock_drift,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            family(&mut out, name, "gauge", help);
+            for (id, sample) in &samples {
+                sample_line(&mut out, name, &device_labels(*id), value(sample));
+            }
+        }
+
+        let requests = self.uhppoted.request_metrics();
+        let counters: [Metric<RequestMetrics, u64>; 3] = [
+            ("uhppote_client_requests_total", "Requests sent.", |m| {
+                m.requests
+            }),
+            (
+                "uhppote_client_timeouts_total",
+                "Requests that didn't get a reply in time.",
+                |m| m.timeouts,
+            ),
+            (
+                "uhppote_client_decode_errors_total",
+                "Received datagrams that couldn't be decoded.",
+                |m| m.decode_errors,
+            ),
+        ];
+        for (name, help, value) in counters {
+            family(&mut out, name, "counter", help);
+            for (message_type, metrics) in &requests {
+                let labels = format!("type=\"{}\"", message_type);
+                sample_line(&mut out, name, &labels, value(metrics));
+            }
+        }
+        let name = "uhppote_client_request_duration_seconds";
+        family(
+            &mut out,
+            name,
+            "summary",
+            "Time until a reply was received.",
+        );
+        for (message_type, metrics) in &requests {
+            let labels = format!("type=\"{}\"", message_type);
+            let sum = format!("{}_sum", name);
+            let count = format!("{}_count", name);
+            sample_line(&mut out, &sum, &labels, metrics.latency.as_secs_f64());
+            sample_line(&mut out, &count, &labels, metrics.responses);
+        }
+        out
+    }
+}
+
+fn device_labels(id: u32) -> String {
+    format!("device_id=\"{}\"", id)
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample_line(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+}
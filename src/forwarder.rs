@@ -0,0 +1,349 @@
+//! A long running service that forwards events from devices as newline-delimited JSON.
+use crate::{Device, Error, EventLogEntry, Result, Status, Uhppoted};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+/// How often [`EventForwarder::run`] checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where an [`EventForwarder`] writes its JSON lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Stdout,
+    /// Append to the file at `path`. Once the file grows beyond `max_bytes`, it is renamed to
+    /// `<path>.1` (shifting older files to `<path>.2` and so on) and a new file is started.
+    /// At most `keep` rotated files are kept.
+    File {
+        path: PathBuf,
+        max_bytes: u64,
+        keep: usize,
+    },
+}
+
+/// Configuration of an [`EventForwarder`].
+#[derive(Debug, Clone)]
+pub struct ForwarderConfig {
+    /// Local address to receive [`Status`] messages on.
+    pub bind: SocketAddr,
+    /// Address the devices should send [`Status`] messages to. This is the address of this host
+    /// as seen by the devices, which differs from `bind` when binding to `0.0.0.0`.
+    pub listener: SocketAddr,
+    /// IDs and (optional) IP addresses of the devices to forward events from.
+    /// A [`Controller`](crate::Controller) registered with [`Uhppoted::controllers`] for one of
+    /// these IDs takes precedence over the address given here.
+    pub devices: Vec<(u32, Option<Ipv4Addr>)>,
+    pub output: Output,
+}
+
+/// Function the [`EventForwarder`] reports errors of a device to.
+type ErrorHandler = Box<dyn FnMut(u32, &Error) + Send>;
+
+/// What the [`EventForwarder`] knows about a device.
+#[derive(Default)]
+struct DeviceState {
+    sequence_number: Option<u32>,
+    last_index: Option<u32>,
+}
+
+/// Forwards events from devices as newline-delimited JSON.
+///
+/// On start, the forwarder registers itself as the listener of every configured device with
+/// [`Device::set_listener`]. Every [`Status`] message received is deduplicated by its sequence
+/// number and event index, and its last event is written as a JSON line. When the event index
+/// skips ahead, the missed events are fetched with [`Device::events_since`] first, so no
+/// events are lost when a [`Status`] message is dropped.
+///
+/// A device that can't be registered as the listener on start, and errors while fetching missed
+/// events, are reported to the handler set with [`EventForwarder::on_error`], by default printed
+/// to stderr. Fetching stops at a timeout and
+/// is retried on the next [`Status`] message. An event that can't be read for another reason,
+/// for instance because it can't be decoded, is written as [`EventLogEntry::Missing`] and
+/// skipped, so it can't hold up the events after it.
+///
+/// Each line is an [`EventLogEntry`] with the `device_id` added, for instance:
+/// ```json
+/// {"device_id":423196779,"kind":"event","index":57,"event_type":"swipe",...}
+/// ```
+///
+/// Example:
+/// ```no_run
+/// use std::sync::atomic::AtomicBool;
+/// use uhppote_rs::{EventForwarder, ForwarderConfig, Output, Uhppoted};
+/// let config = ForwarderConfig {
+///     bind: "0.0.0.0:60001".parse().unwrap(),
+///     listener: "192.168.1.10:60001".parse().unwrap(),
+///     devices: vec![(423196779, None)],
+///     output: Output::Stdout,
+/// };
+/// let mut forwarder = EventForwarder::new(Uhppoted::default(), config);
+/// forwarder.run(&AtomicBool::new(false)).unwrap();
+/// ```
+pub struct EventForwarder {
+    uhppoted: Uhppoted,
+    config: ForwarderConfig,
+    devices: HashMap<u32, DeviceState>,
+    writer: JsonLinesWriter,
+    on_error: ErrorHandler,
+}
+
+impl EventForwarder {
+    pub fn new(uhppoted: Uhppoted, config: ForwarderConfig) -> EventForwarder {
+        let writer = JsonLinesWriter::new(config.output.clone());
+        EventForwarder {
+            uhppoted,
+            config,
+            devices: HashMap::new(),
+            writer,
+            on_error: Box::new(|id, e| eprintln!("device {}: {}", id, e.report())),
+        }
+    }
+
+    /// Report errors of devices to `handler`, with the ID of the device, instead of printing
+    /// them to stderr.
+    pub fn on_error<F: FnMut(u32, &Error) + Send + 'static>(mut self, handler: F) -> Self {
+        self.on_error = Box::new(handler);
+        self
+    }
+
+    /// Register as the listener on all devices and forward events until `stop` is set.
+    pub fn run(&mut self, stop: &AtomicBool) -> Result<()> {
+        let (ip, port) = match self.config.listener {
+            SocketAddr::V4(a) => (*a.ip(), a.port()),
+            SocketAddr::V6(a) => {
+                return Err(Error::InvalidArgument(format!(
+                    "listener must be an IPv4 address, got {}",
+                    a
+                )))
+            }
+        };
+
+        let mut listener = self.uhppoted.listener(self.config.bind)?;
+        for (id, address) in &self.config.devices {
+            listener = listener.device(*id);
+            // One unreachable device is no reason not to forward the events of the others.
+            if let Err(e) = self
+                .uhppoted
+                .listed_device(*id, *address)
+                .set_listener(ip, port)
+            {
+                (self.on_error)(*id, &e);
+            }
+        }
+        let (handle, statuses) = listener.channel()?;
+
+        while !stop.load(Ordering::Relaxed) {
+            match statuses.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(status)) => self.handle(status)?,
+                // Undecodable packets are not worth stopping for.
+                Ok(Err(_)) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        handle.stop();
+        Ok(())
+    }
+
+    fn device(&self, id: u32) -> Device<'_> {
+        let address = self
+            .config
+            .devices
+            .iter()
+            .find(|(d, _)| *d == id)
+            .and_then(|(_, address)| *address);
+        self.uhppoted.listed_device(id, address)
+    }
+
+    fn handle(&mut self, status: Status) -> Result<()> {
+        let id = status.device_id;
+        let state = self.devices.entry(id).or_default();
+        if state.sequence_number == Some(status.sequence_number) {
+            return Ok(());
+        }
+        state.sequence_number = Some(status.sequence_number);
+
+        let event = match status.last_event {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        let last_index = state.last_index;
+        match last_index {
+            Some(last) if event.index <= last => Ok(()),
+            Some(last) if event.index > last + 1 => self.backfill(id, last),
+            _ => {
+                self.devices.entry(id).or_default().last_index = Some(event.index);
+                self.write(id, EventLogEntry::Event(event))
+            }
+        }
+    }
+
+    /// Fetch and write all events after `last`, including the one that revealed the gap. When
+    /// fetching times out, the rest of the gap is retried on the next [`Status`] message.
+    fn backfill(&mut self, id: u32, last: u32) -> Result<()> {
+        let (entries, errors) = fetch_since(&self.device(id), last);
+        for e in &errors {
+            (self.on_error)(id, e);
+        }
+        let cursor = entries.last().map(EventLogEntry::last_index);
+        for entry in entries {
+            self.write(id, entry)?;
+        }
+        if let Some(cursor) = cursor {
+            self.devices.entry(id).or_default().last_index = Some(cursor);
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, device_id: u32, entry: EventLogEntry) -> Result<()> {
+        let mut line = serde_json::to_value(entry).map_err(|e| Error::Decode(e.to_string()))?;
+        if let Value::Object(fields) = &mut line {
+            fields.insert("device_id".to_string(), device_id.into());
+        }
+        Ok(self.writer.write_line(&line.to_string())?)
+    }
+}
+
+/// Fetch the entries of `device` after `cursor`. Fetching stops at the first transient error,
+/// other errors are recorded as a missing event and skipped.
+fn fetch_since(device: &Device, mut cursor: u32) -> (Vec<EventLogEntry>, Vec<Error>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    loop {
+        let mut events = match device.events_since(cursor) {
+            Ok(events) => events,
+            Err(e) => {
+                errors.push(e);
+                break;
+            }
+        };
+        let error = events.by_ref().find_map(|entry| match entry {
+            Ok(entry) => {
+                entries.push(entry);
+                None
+            }
+            Err(e) => Some(e),
+        });
+        match error {
+            Some(e) if !e.cause().is_transient() => {
+                // The iterator ends at the index that failed, right after its cursor.
+                cursor = events.cursor() + 1;
+                entries.push(EventLogEntry::Missing {
+                    from: cursor,
+                    to: cursor,
+                });
+                errors.push(e);
+                if cursor >= events.end_index() {
+                    break;
+                }
+            }
+            Some(e) => {
+                errors.push(e);
+                break;
+            }
+            None => break,
+        }
+    }
+    (entries, errors)
+}
+
+/// Writes lines to an [`Output`], rotating files when they get too big.
+struct JsonLinesWriter {
+    output: Output,
+    file: Option<File>,
+    size: u64,
+}
+
+impl JsonLinesWriter {
+    fn new(output: Output) -> JsonLinesWriter {
+        JsonLinesWriter {
+            output,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let (path, max_bytes, keep) = match &self.output {
+            Output::Stdout => {
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{}", line)?;
+                return stdout.flush();
+            }
+            Output::File {
+                path,
+                max_bytes,
+                keep,
+            } => (path.clone(), *max_bytes, *keep),
+        };
+
+        if self.file.is_some() && self.size >= max_bytes {
+            self.file = None;
+            rotate(&path, keep)?;
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                let file = OpenOptions::new().create(true).append(true).open(&path)?;
+                self.size = file.metadata()?.len();
+                self.file.insert(file)
+            }
+        };
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Shift `<path>.N` to `<path>.N+1` (dropping the oldest) and move `path` to `<path>.1`.
+fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    let rotated = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    let _ = fs::remove_file(rotated(keep));
+    for n in (1..keep).rev() {
+        if rotated(n).exists() {
+            fs::rename(rotated(n), rotated(n + 1))?;
+        }
+    }
+    fs::rename(path, rotated(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation() {
+        let dir = std::env::temp_dir().join(format!("uhppote-rotation-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.jsonl");
+        let mut writer = JsonLinesWriter::new(Output::File {
+            path: path.clone(),
+            max_bytes: 10,
+            keep: 2,
+        });
+
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            writer.write_line(line).unwrap();
+        }
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("events.jsonl"), "fourth line\n");
+        assert_eq!(read("events.jsonl.1"), "third line\n");
+        assert_eq!(read("events.jsonl.2"), "second line\n");
+        assert!(!dir.join("events.jsonl.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
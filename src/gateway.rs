@@ -0,0 +1,297 @@
+//! An HTTP server exposing devices as a REST API with JSON bodies.
+use crate::{Card, Device, DoorControl, Error, Result, Task, TimeProfile, Uhppoted};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// How often [`HttpGateway::run`] checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Default for [`HttpGateway::max_requests`].
+const MAX_REQUESTS: usize = 64;
+
+/// An HTTP server that exposes devices as a REST API.
+///
+/// | Method | Path | Body | Device operation |
+/// |--------|------|------|------------------|
+/// | GET | `/devices` | | [`Uhppoted::get_device_configs`] |
+/// | GET | `/devices/{id}` | | [`Device::get_config`] |
+/// | GET | `/devices/{id}/status` | | [`Device::get_status`] |
+/// | GET, PUT | `/devices/{id}/time` | `"2023-05-04T12:34:56"` | [`Device::get_time`], [`Device::set_time`] |
+/// | GET, PUT | `/devices/{id}/listener` | `"192.168.1.10:60001"` | [`Device::get_listener`], [`Device::set_listener`] |
+/// | GET | `/devices/{id}/cards` | | [`Device::get_all_cards`] |
+/// | DELETE | `/devices/{id}/cards` | | [`Device::clear_cards`] |
+/// | GET, PUT, DELETE | `/devices/{id}/cards/{number}` | [`Card`] | [`Device::get_card_by_id`], [`Device::add_card`], [`Device::delete_card`] |
+/// | GET, PUT | `/devices/{id}/doors/{door}` | [`DoorControl`] | [`Device::get_door_control`], [`Device::set_door_control_state`] |
+/// | POST | `/devices/{id}/doors/{door}/open` | | [`Device::open_door`] |
+/// | GET | `/devices/{id}/events?from={index}` | | [`Device::events_since`], or [`Device::new_events`] without `from` |
+/// | GET | `/devices/{id}/events/{index}` | | [`Device::get_event`] |
+/// | DELETE | `/devices/{id}/time-profiles` | | [`Device::clear_time_profiles`] |
+/// | GET, PUT | `/devices/{id}/time-profiles/{profile}` | [`TimeProfile`] | [`Device::get_time_profile`], [`Device::add_or_update_time_profile`] |
+/// | POST, DELETE | `/devices/{id}/tasks` | [`Task`] | [`Device::add_task`], [`Device::clear_tasks`] |
+/// | POST | `/devices/{id}/tasks/refresh` | | [`Device::refresh_task_list`] |
+///
+/// Errors are returned as `{"error": "..."}`, with status 400 for invalid requests, 422 when
+/// the device rejects an operation, 504 when the device doesn't respond and 502 for other device
+/// errors. Requests beyond [`HttpGateway::max_requests`] are answered with 503.
+///
+/// Example:
+/// ```no_run
+/// use std::sync::atomic::AtomicBool;
+/// use uhppote_rs::{HttpGateway, Uhppoted};
+/// let gateway = HttpGateway::bind(
+///     Uhppoted::default(),
+///     vec![(423196779, Some("192.168.1.100".parse().unwrap()))],
+///     "0.0.0.0:8080".parse().unwrap(),
+/// )
+/// .unwrap();
+/// gateway.run(&AtomicBool::new(false)).unwrap();
+/// ```
+pub struct HttpGateway {
+    uhppoted: Uhppoted,
+    devices: Vec<(u32, Option<Ipv4Addr>)>,
+    server: Server,
+    max_requests: usize,
+    in_progress: AtomicUsize,
+}
+
+/// A response: status code and JSON body.
+type Reply = (u16, Value);
+
+impl HttpGateway {
+    /// Create an [`HttpGateway`] listening on `address`. `devices` are the IDs and IP addresses
+    /// of known devices; other devices are reached through broadcast. A
+    /// [`Controller`](crate::Controller) registered with [`Uhppoted::controllers`] takes
+    /// precedence over `devices`.
+    pub fn bind(
+        uhppoted: Uhppoted,
+        devices: Vec<(u32, Option<Ipv4Addr>)>,
+        address: SocketAddr,
+    ) -> Result<HttpGateway> {
+        let server =
+            Server::http(address).map_err(|e| Error::Io(io::Error::other(e.to_string())))?;
+        Ok(HttpGateway {
+            uhppoted,
+            devices,
+            server,
+            max_requests: MAX_REQUESTS,
+            in_progress: AtomicUsize::new(0),
+        })
+    }
+
+    /// Handle at most `max_requests` requests at a time, 64 by default. Requests that arrive while
+    /// as many are in progress are answered with 503 right away.
+    pub fn max_requests(mut self, max_requests: usize) -> HttpGateway {
+        self.max_requests = max_requests;
+        self
+    }
+
+    /// The address the [`HttpGateway`] is listening on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Serve requests until `stop` is set. Each request is handled on a thread of its own, up to
+    /// [`HttpGateway::max_requests`] at a time, so a device that doesn't respond only holds up
+    /// the requests for that device. Returns once all requests in progress are answered.
+    pub fn run(&self, stop: &AtomicBool) -> Result<()> {
+        std::thread::scope(|s| {
+            while !stop.load(Ordering::Relaxed) {
+                match self.server.recv_timeout(POLL_INTERVAL)? {
+                    Some(request) => {
+                        // Only this loop adds to `in_progress`, so it can't exceed the limit.
+                        if self.in_progress.load(Ordering::Acquire) < self.max_requests {
+                            self.in_progress.fetch_add(1, Ordering::AcqRel);
+                            s.spawn(move || {
+                                self.respond(request);
+                                self.in_progress.fetch_sub(1, Ordering::AcqRel);
+                            });
+                        } else {
+                            send(request, error(503, "too many requests in progress"));
+                        }
+                    }
+                    None => continue,
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn respond(&self, mut request: Request) {
+        let mut body = String::new();
+        let reply = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => self.handle(request.method(), request.url(), &body),
+            Err(e) => error(400, e),
+        };
+        send(request, reply);
+    }
+
+    fn device(&self, id: u32) -> Device<'_> {
+        let address = self
+            .devices
+            .iter()
+            .find(|(d, _)| *d == id)
+            .and_then(|(_, address)| *address);
+        self.uhppoted.listed_device(id, address)
+    }
+
+    fn handle(&self, method: &Method, url: &str, body: &str) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match segments.as_slice() {
+            ["devices"] => match method {
+                Method::Get => reply(self.uhppoted.get_device_configs()),
+                _ => method_not_allowed(),
+            },
+            ["devices", id, rest @ ..] => match id.parse() {
+                Ok(id) => self.handle_device(&self.device(id), method, rest, query, body),
+                Err(e) => error(400, e),
+            },
+            _ => error(404, "not found"),
+        }
+    }
+
+    fn handle_device(
+        &self,
+        device: &Device,
+        method: &Method,
+        path: &[&str],
+        query: &str,
+        body: &str,
+    ) -> Reply {
+        match (method, path) {
+            (Method::Get, []) => reply(device.get_config()),
+            (Method::Get, ["status"]) => reply(device.get_status()),
+            (Method::Get, ["time"]) => reply(device.get_time()),
+            (Method::Put, ["time"]) => with_body(body, |datetime| device.set_time(datetime)),
+            (Method::Get, ["listener"]) => reply(device.get_listener()),
+            (Method::Put, ["listener"]) => with_body(body, |address: SocketAddr| match address {
+                SocketAddr::V4(a) => device.set_listener(*a.ip(), a.port()),
+                SocketAddr::V6(_) => Err(Error::InvalidArgument(
+                    "listener must be an IPv4 address".to_string(),
+                )),
+            }),
+            (Method::Get, ["cards"]) => reply(device.get_all_cards()),
+            (Method::Delete, ["cards"]) => reply(device.clear_cards()),
+            (method, ["cards", number]) => match number.parse() {
+                Ok(number) => match method {
+                    Method::Get => reply(device.get_card_by_id(number)),
+                    Method::Put => {
+                        with_body(body, |card: Card| device.add_card(Card { number, ..card }))
+                    }
+                    Method::Delete => reply(device.delete_card(number)),
+                    _ => method_not_allowed(),
+                },
+                Err(e) => error(400, e),
+            },
+            (method, ["doors", door, rest @ ..]) => match door.parse() {
+                Ok(door) => match (method, rest) {
+                    (Method::Get, []) => reply(device.get_door_control(door)),
+                    (Method::Put, []) => with_body(body, |state: DoorControl| {
+                        device.set_door_control_state(door, state)
+                    }),
+                    (Method::Post, ["open"]) => reply(device.open_door(door)),
+                    (_, [] | ["open"]) => method_not_allowed(),
+                    _ => error(404, "not found"),
+                },
+                Err(e) => error(400, e),
+            },
+            (Method::Get, ["events"]) => {
+                let from = query
+                    .split('&')
+                    .find_map(|p| p.strip_prefix("from="))
+                    .map(|from| from.parse::<u32>());
+                let events = match from {
+                    Some(Ok(cursor)) => device.events_since(cursor),
+                    Some(Err(e)) => return error(400, e),
+                    None => device.new_events(),
+                };
+                reply(events.and_then(|events| events.collect::<Result<Vec<_>>>()))
+            }
+            (Method::Get, ["events", index]) => match index.parse() {
+                Ok(index) => reply(device.get_event(index)),
+                Err(e) => error(400, e),
+            },
+            (Method::Delete, ["time-profiles"]) => reply(device.clear_time_profiles()),
+            (method, ["time-profiles", id]) => match id.parse() {
+                Ok(id) => match method {
+                    Method::Get => reply(device.get_time_profile(id)),
+                    Method::Put => with_body(body, |profile: TimeProfile| {
+                        device.add_or_update_time_profile(TimeProfile { id, ..profile })
+                    }),
+                    _ => method_not_allowed(),
+                },
+                Err(e) => error(400, e),
+            },
+            (Method::Post, ["tasks"]) => with_body(body, |task: Task| device.add_task(task)),
+            (Method::Delete, ["tasks"]) => reply(device.clear_tasks()),
+            (Method::Post, ["tasks", "refresh"]) => reply(device.refresh_task_list()),
+            (
+                _,
+                []
+                | ["status" | "time" | "listener" | "cards" | "events" | "time-profiles" | "tasks"]
+                | ["events", _]
+                | ["tasks", "refresh"],
+            ) => method_not_allowed(),
+            _ => error(404, "not found"),
+        }
+    }
+}
+
+/// Answer `request` with `status` and the JSON body `value`.
+fn send(request: Request, (status, value): Reply) {
+    let content_type =
+        Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).expect("valid header");
+    let response = Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+    // The client may have gone away; that's no reason to stop serving others.
+    let _ = request.respond(response);
+}
+
+/// Parse `body` as JSON and pass it to `f`.
+fn with_body<T, U, F>(body: &str, f: F) -> Reply
+where
+    T: DeserializeOwned,
+    U: Serialize,
+    F: FnOnce(T) -> Result<U>,
+{
+    match serde_json::from_str(body) {
+        Ok(value) => reply(f(value)),
+        Err(e) => error(400, e),
+    }
+}
+
+fn reply<T: Serialize>(result: Result<T>) -> Reply {
+    match result {
+        Ok(value) => match serde_json::to_value(value) {
+            // Operations without a result return `()`, which serializes as `null`.
+            Ok(Value::Null) => (200, json!({ "ok": true })),
+            Ok(value) => (200, value),
+            Err(e) => error(500, e),
+        },
+        Err(e) => {
+            let status = match e.cause() {
+                Error::InvalidArgument(_) => 400,
+                Error::Rejected { .. } => 422,
+                Error::Timeout => 504,
+                _ => 502,
+            };
+            error(status, e.report())
+        }
+    }
+}
+
+fn error(status: u16, e: impl ToString) -> Reply {
+    (status, json!({ "error": e.to_string() }))
+}
+
+fn method_not_allowed() -> Reply {
+    error(405, "method not allowed")
+}
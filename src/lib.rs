@@ -12,34 +12,102 @@
 //! let device = uhppoted.get_device(423196779, None);
 //! let status = device.get_status().unwrap();
 //! ```
+//!
+//! With the `tokio` feature enabled, [`AsyncUhppoted`] and [`AsyncDevice`] offer the same
+//! operations as `async fn`s. With the `serde` feature enabled, the public types implement
+//! `Serialize` and `Deserialize`, with enums represented as `snake_case` strings. With the `http`
+//! feature enabled, [`HttpGateway`] serves the operations of devices as a REST API. With the `mqtt`
+//! feature enabled, [`MqttBridge`] publishes events to and takes commands from an MQTT broker.
+//! With the `prometheus` feature enabled, [`MetricsExporter`] serves metrics of devices. With the
+//! `config` feature enabled, [`Controller::load`] reads registered controllers from a file.
+#[cfg(feature = "tokio")]
+mod async_client;
+mod card_sync;
+mod cards;
+mod discovery;
+mod dispatcher;
+mod error;
+mod event_log;
+#[cfg(feature = "prometheus")]
+mod exporter;
+#[cfg(feature = "daemon")]
+mod forwarder;
+#[cfg(feature = "http")]
+mod gateway;
+mod listener;
 mod messages;
+mod metrics;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod registry;
+mod retry;
+#[cfg(feature = "serde")]
+mod seconds;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+mod snapshot;
+mod transport;
 mod types;
-use anyhow::bail;
-use anyhow::Result;
+pub use card_sync::{CardChange, CardSyncPlan, CardSyncResult};
+pub use cards::{Cards, CardsProgress};
 use chrono::Datelike;
 pub use chrono::NaiveDate;
 pub use chrono::NaiveDateTime;
 pub use chrono::NaiveTime;
+pub use discovery::DiscoveredDevice;
+use dispatcher::Dispatcher;
+pub use error::{Error, Result};
+pub use event_log::{EventLogEntry, Events};
+#[cfg(feature = "prometheus")]
+pub use exporter::MetricsExporter;
+#[cfg(feature = "daemon")]
+pub use forwarder::{EventForwarder, ForwarderConfig, Output};
+#[cfg(feature = "http")]
+pub use gateway::HttpGateway;
+pub use listener::{Listener, ListenerHandle};
 use messages::types::DateBCD;
 use messages::*;
+use metrics::Metrics;
+pub use metrics::RequestMetrics;
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttBridge, MqttConfig};
+use registry::Registry;
+pub use registry::{Controller, Protocol};
+pub use retry::RetryPolicy;
+pub use snapshot::DeviceSnapshot;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
-use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use std::time::Instant;
+pub use transport::{MockTransport, TcpTransport, Transport, UdpTransport};
 pub use types::*;
 
+#[cfg(feature = "tokio")]
+pub use async_client::{AsyncDevice, AsyncUhppoted};
+
 const UHPPOTE_PORT: u16 = 60000;
 #[derive(Debug)]
 pub struct Uhppoted {
     bind_address: SocketAddr,
     broadcast_address: Ipv4Addr,
     timeout: Duration,
+    retry_policy: RetryPolicy,
+    metrics: Metrics,
+    dispatcher: Mutex<Option<Arc<Dispatcher>>>,
+    transport: Option<Arc<dyn Transport>>,
+    registry: Registry,
 }
 
 impl Uhppoted {
     /// Create a new Uhppote struct
     ///
+    /// All requests are sent from a single socket, which is bound to `bind` on first use. `bind`
+    /// can therefore be a fixed port, and [`Device`]s can be used from many threads at the same
+    /// time.
+    ///
     /// Example:
     /// ```no_run
     /// use uhppote_rs::Uhppoted;
@@ -53,24 +121,93 @@ impl Uhppoted {
             bind_address: bind,
             broadcast_address: broadcast,
             timeout,
+            retry_policy: RetryPolicy::default(),
+            metrics: Metrics::default(),
+            dispatcher: Mutex::new(None),
+            transport: None,
+            registry: Registry::default(),
+        }
+    }
+
+    /// The [`Dispatcher`] owning the socket all requests are sent from. The socket is bound on
+    /// first use, so a failure to bind is reported by the request that needed it.
+    fn dispatcher(&self) -> Result<Arc<Dispatcher>> {
+        let mut dispatcher = self.dispatcher.lock().unwrap_or_else(|e| e.into_inner());
+        match &*dispatcher {
+            Some(d) => Ok(d.clone()),
+            None => Ok(dispatcher
+                .insert(Arc::new(Dispatcher::bind(self.bind_address)?))
+                .clone()),
+        }
+    }
+
+    /// Set the [`RetryPolicy`] for requests to devices. By default, requests that only read from
+    /// a device are sent up to 3 times, see [`RetryPolicy::default`].
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Uhppoted {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Send all requests through `transport` instead of over UDP, unless a [`Device`] has a
+    /// [`Transport`] of its own. Discovery requests are sent through `transport` as well.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use uhppote_rs::{TcpTransport, Uhppoted};
+    /// let transport = TcpTransport::new("192.168.1.100:60000".parse().unwrap());
+    /// let uhppoted = Uhppoted::default().transport(Arc::new(transport));
+    /// ```
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Uhppoted {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Register how to reach `controllers`: their address, port, protocol and timeout.
+    /// [`Uhppoted::get_device`] looks them up by device ID, so only unregistered devices without
+    /// an IP address are reached by broadcast. Registering a device ID again replaces the earlier
+    /// registration.
+    ///
+    /// Controllers reached over UDP use the [`Transport`] set with [`Uhppoted::transport`], if
+    /// any.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use uhppote_rs::{Controller, Protocol, Uhppoted};
+    /// let uhppoted = Uhppoted::default().controllers([Controller {
+    ///     port: 60005,
+    ///     protocol: Protocol::Tcp,
+    ///     timeout: Some(Duration::from_secs(10)),
+    ///     ..Controller::new(423196779, "203.0.113.10".parse().unwrap())
+    /// }]);
+    /// let status = uhppoted.get_device(423196779, None).get_status().unwrap();
+    /// ```
+    pub fn controllers(mut self, controllers: impl IntoIterator<Item = Controller>) -> Uhppoted {
+        for controller in controllers {
+            self.registry.insert(controller);
         }
+        self
+    }
+
+    /// [`RequestMetrics`] of the requests sent through this [`Uhppoted`], keyed by message type
+    /// (e.g. `GetStatus`). Message types that were never sent are left out.
+    pub fn request_metrics(&self) -> BTreeMap<String, RequestMetrics> {
+        self.metrics.snapshot()
     }
 
     /// Get all the available [`DeviceConfig`]s on the local network. This broadcasts a discovery message
-    /// and waits [`Uhppoted::timeout`] for responses.
+    /// and waits [`Uhppoted::timeout`] for responses. Use [`Uhppoted::discover`] to search all
+    /// networks this host is attached to.
     pub fn get_device_configs(&self) -> Result<Vec<DeviceConfig>> {
         let request = GetConfigRequest::new(0);
         let response: Vec<GetConfigResponse> = broadcast_and_receive(request, self)?;
-        let r = response
-            .into_iter()
-            .map(|r| r.try_into().unwrap())
-            .collect();
-        Ok(r)
+        response.into_iter().map(|r| r.try_into()).collect()
     }
 
     /// Get all the available [`Device`]s on the local network. This broadcasts a discovery message
     /// and waits [`Uhppoted::timeout`] for responses.
-    pub fn get_devices(&self) -> Result<Vec<Device>> {
+    pub fn get_devices(&self) -> Result<Vec<Device<'_>>> {
         let request = GetConfigRequest::new(0);
         let response: Vec<GetConfigResponse> = broadcast_and_receive(request, self)?;
         let r = response
@@ -84,14 +221,43 @@ impl Uhppoted {
     /// merely represents a device to interact with.
     ///
     /// When `ip_address` is specified, communication will happen directly with the device. Otherwise,
-    /// communication to the device will happen via local network broadcast.
+    /// the address of the [`Controller`] registered with [`Uhppoted::controllers`] is used and,
+    /// for unregistered devices, communication to the device will happen via local network
+    /// broadcast. The port, protocol and timeout of a registered [`Controller`] apply either way.
+    ///
+    /// Specify an `ip_address` or register a [`Controller`] when the device is not on the local
+    /// network.
     ///
-    /// Specify an `ip_address` when the device is not on the local network.
-    pub fn get_device(&self, id: u32, ip_address: Option<Ipv4Addr>) -> Device {
+    /// For a [`Controller`] reached over TCP, an `ip_address` other than the registered one gets
+    /// a connection of its own to that address, instead of the shared connection of the
+    /// [`Controller`].
+    pub fn get_device(&self, id: u32, ip_address: Option<Ipv4Addr>) -> Device<'_> {
         Device::new(self, id, ip_address)
     }
 
+    /// Get the [`Device`] of a [`Controller`] registered with [`Uhppoted::controllers`], or
+    /// `None` when no [`Controller`] is registered for `id`.
+    pub fn device(&self, id: u32) -> Option<Device<'_>> {
+        self.registry.get(id).map(|_| Device::new(self, id, None))
+    }
+
+    /// Get the [`Device`] for an entry of the device list of a service. A [`Controller`]
+    /// registered for `id` takes precedence over the `ip_address` of the entry.
+    #[cfg(any(
+        feature = "daemon",
+        feature = "http",
+        feature = "mqtt",
+        feature = "prometheus"
+    ))]
+    pub(crate) fn listed_device(&self, id: u32, ip_address: Option<Ipv4Addr>) -> Device<'_> {
+        self.device(id)
+            .unwrap_or_else(|| self.get_device(id, ip_address))
+    }
+
     /// Listen for incoming [`Status`] messages from the UHPPOTE system on a specific `address`.
+    /// This blocks forever and skips messages that can't be decoded. Use [`Uhppoted::listener`]
+    /// for a listener that can be stopped and reports errors.
+    ///
     /// Example:
     /// ```no_run
     /// use uhppote_rs::Uhppoted;
@@ -104,21 +270,14 @@ impl Uhppoted {
     ///     println!("{:?}", status);
     /// });
     /// ```
-    pub fn listen(&self, address: SocketAddr, handler: fn(Status)) -> Result<()> {
-        let socket = UdpSocket::bind(&address)?;
-        socket.set_broadcast(true)?;
-        socket.set_read_timeout(None)?;
-        loop {
-            let mut buf = [0u8; 64];
-            socket.recv(&mut buf)?;
-            match buf[1].try_into()? {
-                RequestResponseType::Status => {
-                    let response = GetStatusResponse::from_bytes(&buf)?;
-                    handler(response.try_into()?);
-                }
-                response_type => bail!("Can't listen for {:?}", response_type),
-            }
-        }
+    pub fn listen<F: FnMut(Status)>(&self, address: SocketAddr, handler: F) -> Result<()> {
+        Listener::bind(address)?.run(handler)
+    }
+
+    /// Create a [`Listener`] for incoming [`Status`] messages on a specific `address`. The
+    /// [`Listener`] can be filtered by device and runs on a background thread until it is stopped.
+    pub fn listener(&self, address: SocketAddr) -> Result<Listener> {
+        Listener::bind(address)
     }
 }
 
@@ -129,7 +288,7 @@ impl Default for Uhppoted {
     ///   - `broadcast`: 255.255.255.255
     ///   - `listen`: None
     ///   - `timeout`: Duration::new(5, 0)
-    ///   - `controllers`: None
+    ///   - `controllers`: none registered
     fn default() -> Uhppoted {
         Uhppoted::new(
             "0.0.0.0:0".parse().unwrap(),
@@ -144,16 +303,80 @@ pub struct Device<'a> {
     u: &'a Uhppoted,
     id: u32,
     ip_address: Option<Ipv4Addr>,
+    port: u16,
+    timeout: Option<Duration>,
+    transport: Option<Arc<dyn Transport>>,
 }
 
 impl<'a> Device<'a> {
-    /// Create a new [`Device`] from an [`Uhppoted`] and a device ID.
+    /// Create a new [`Device`] from an [`Uhppoted`] and a device ID, completed with the
+    /// registered [`Controller`] for the ID, if any.
     fn new(u: &'a Uhppoted, id: u32, ip_address: Option<Ipv4Addr>) -> Device<'a> {
-        Device { u, id, ip_address }
+        match u.registry.get(id) {
+            Some(r) => {
+                let address = ip_address.unwrap_or(r.controller.address);
+                // Only controllers reached over TCP have a transport of their own, connected to
+                // the registered address.
+                let transport: Option<Arc<dyn Transport>> = match &r.transport {
+                    Some(_) if address != r.controller.address => {
+                        let address = SocketAddr::new(address.into(), r.controller.port);
+                        Some(Arc::new(TcpTransport::new(address)))
+                    }
+                    transport => transport.clone(),
+                };
+                Device {
+                    u,
+                    id,
+                    ip_address: Some(address),
+                    port: r.controller.port,
+                    timeout: r.controller.timeout,
+                    transport,
+                }
+            }
+            None => Device {
+                u,
+                id,
+                ip_address,
+                port: UHPPOTE_PORT,
+                timeout: None,
+                transport: None,
+            },
+        }
+    }
+
+    /// Send the requests for this [`Device`] through `transport`, instead of the [`Transport`]
+    /// of the [`Uhppoted`] or UDP.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use uhppote_rs::{TcpTransport, Uhppoted};
+    /// let uhppoted = Uhppoted::default();
+    /// let transport = TcpTransport::new("192.168.1.100:60000".parse().unwrap());
+    /// let device = uhppoted.get_device(423196779, None).transport(Arc::new(transport));
+    /// ```
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Device<'a> {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Enable or disable the keypads of the readers of doors 1-4.
+    pub fn activate_keypads(&self, keypads: [bool; 4]) -> Result<()> {
+        let request =
+            ActivateKeypadsRequest::new(self.id, keypads[0], keypads[1], keypads[2], keypads[3]);
+        let response: ActivateKeypadsResponse = send_and_receive(request, self)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "ActivateKeypads",
+            })
+        }
     }
 
     /// Add a [`Card`] to the [`Device`].
     pub fn add_card(&self, card: Card) -> Result<()> {
+        check_card(&card)?;
         let request = PutCardRequest::new(
             self.id,
             card.number,
@@ -163,12 +386,15 @@ impl<'a> Device<'a> {
             card.doors[1],
             card.doors[2],
             card.doors[3],
+            card.pin.into(),
         );
         let response: PutCardResponse = send_and_receive(request, self)?;
         if response.success {
             Ok(())
         } else {
-            bail!("PutCard failed")
+            Err(Error::Rejected {
+                operation: "PutCard",
+            })
         }
     }
 
@@ -204,7 +430,9 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("AddTask failed")
+            Err(Error::Rejected {
+                operation: "AddTask",
+            })
         }
     }
 
@@ -216,7 +444,9 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("DeleteCard failed")
+            Err(Error::Rejected {
+                operation: "DeleteCards",
+            })
         }
     }
 
@@ -227,7 +457,9 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("ClearTaskList failed")
+            Err(Error::Rejected {
+                operation: "ClearTaskList",
+            })
         }
     }
 
@@ -239,7 +471,9 @@ impl<'a> Device<'a> {
         if response.magic_word == magic_word {
             Ok(())
         } else {
-            bail!("ClearTimeProfiles failed")
+            Err(Error::Rejected {
+                operation: "ClearTimeProfiles",
+            })
         }
     }
 
@@ -250,10 +484,19 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("DeleteCard failed")
+            Err(Error::Rejected {
+                operation: "DeleteCard",
+            })
         }
     }
 
+    /// Get the [`AntiPassback`] mode of the [`Device`].
+    pub fn get_antipassback(&self) -> Result<AntiPassback> {
+        let request = GetAntiPassbackRequest::new(self.id);
+        let response: GetAntiPassbackResponse = send_and_receive(request, self)?;
+        response.antipassback.try_into()
+    }
+
     /// Get a specific [`Card`] by its ID.
     pub fn get_card_by_id(&self, id: u32) -> Result<Card> {
         let request = GetCardByIDRequest::new(self.id, id);
@@ -268,6 +511,17 @@ impl<'a> Device<'a> {
         response.try_into()
     }
 
+    /// Iterate over all [`Card`]s stored on the [`Device`]. See [`Cards`] for details.
+    pub fn cards(&self) -> Result<Cards<'_>> {
+        Ok(Cards::new(self, self.get_cards()?))
+    }
+
+    /// Get all [`Card`]s stored on the [`Device`]. This is a shortcut for collecting
+    /// [`Device::cards`].
+    pub fn get_all_cards(&self) -> Result<Vec<Card>> {
+        self.cards()?.collect()
+    }
+
     /// Get the number of [`Card`]s from the [`Device`].
     pub fn get_cards(&self) -> Result<u32> {
         let request = GetCardsRequest::new(self.id);
@@ -342,7 +596,9 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("OpenDoor failed")
+            Err(Error::Rejected {
+                operation: "OpenDoor",
+            })
         }
     }
 
@@ -353,13 +609,29 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("RefreshTaskList failed")
+            Err(Error::Rejected {
+                operation: "RefreshTaskList",
+            })
+        }
+    }
+
+    /// Set the [`AntiPassback`] mode of the [`Device`].
+    pub fn set_antipassback(&self, antipassback: AntiPassback) -> Result<()> {
+        let request = SetAntiPassbackRequest::new(self.id, antipassback as u8);
+        let response: SetAntiPassbackResponse = send_and_receive(request, self)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetAntiPassback",
+            })
         }
     }
 
     /// Set the [`DoorControl`] for a specific door.
     /// Note that the delay is in seconds and can maximally be 255.
     pub fn set_door_control_state(&self, door: u8, state: DoorControl) -> Result<DoorControl> {
+        check_delay(&state)?;
         let request = SetDoorControlStateRequest::new(
             self.id,
             door,
@@ -370,6 +642,29 @@ impl<'a> Device<'a> {
         Ok(response.into())
     }
 
+    /// Set the (up to four) supervisor passcodes of a door. Passcodes are at most 6 digits; a
+    /// passcode of 0 means "unused".
+    /// Note that doors are addressed 1-4, not 0-3.
+    pub fn set_door_passcodes(&self, door: u8, passcodes: [u32; 4]) -> Result<()> {
+        check_passcodes(door, &passcodes)?;
+        let request = SetDoorPasscodesRequest::new(
+            self.id,
+            door,
+            passcodes[0],
+            passcodes[1],
+            passcodes[2],
+            passcodes[3],
+        );
+        let response: SetDoorPasscodesResponse = send_and_receive(request, self)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetDoorPasscodes",
+            })
+        }
+    }
+
     /// Set the event index the [`Device`] will use.
     pub fn set_event_index(&self, index: u32) -> Result<()> {
         let request = SetEventIndexRequest::new(self.id, index, 0x55aaaa55);
@@ -377,7 +672,51 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("SetEventIndex failed")
+            Err(Error::Rejected {
+                operation: "SetEventIndex",
+            })
+        }
+    }
+
+    /// Set the [`FirstCardConfig`] of a door.
+    /// Note that doors are addressed 1-4, not 0-3.
+    pub fn set_first_card(&self, config: FirstCardConfig) -> Result<()> {
+        check_door(config.door)?;
+        let request = SetFirstCardRequest::new(
+            self.id,
+            config.door,
+            config.start.try_into()?,
+            config.start_mode as u8,
+            config.end.try_into()?,
+            config.end_mode as u8,
+            config.monday,
+            config.tuesday,
+            config.wednesday,
+            config.thursday,
+            config.friday,
+            config.saturday,
+            config.sunday,
+        );
+        let response: SetFirstCardResponse = send_and_receive(request, self)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetFirstCard",
+            })
+        }
+    }
+
+    /// Set the door [`Interlock`] of the [`Device`].
+    pub fn set_interlock(&self, interlock: Interlock) -> Result<()> {
+        let request = SetInterlockRequest::new(self.id, interlock as u8);
+        let response: SetInterlockResponse = send_and_receive(request, self)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                operation: "SetInterlock",
+            })
         }
     }
 
@@ -388,7 +727,9 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("SetListener failed")
+            Err(Error::Rejected {
+                operation: "SetListener",
+            })
         }
     }
 
@@ -411,7 +752,9 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("SetRecordSpecialEvents failed")
+            Err(Error::Rejected {
+                operation: "SetRecordSpecialEvents",
+            })
         }
     }
 
@@ -448,41 +791,144 @@ impl<'a> Device<'a> {
         if response.success {
             Ok(())
         } else {
-            bail!("SetTimeProfile failed")
+            Err(Error::Rejected {
+                operation: "SetTimeProfile",
+            })
         }
     }
 }
 
-/// Send a [`Request`] and receive a [`Response`].
+/// Send a [`Request`] and receive a [`Response`], retrying according to the [`RetryPolicy`] of
+/// the [`Uhppoted`].
 fn send_and_receive<T: messages::Request, S: messages::Response + Debug>(
     request: T,
     d: &Device,
 ) -> Result<S> {
-    let socket = setup_socket(d.u)?;
-    let addr = get_address(d);
-    socket.send_to(
-        &request.to_bytes(),
-        &SocketAddr::new(addr.into(), UHPPOTE_PORT),
-    )?;
-
-    // Receive the response
-    let mut buf = [0u8; 64];
-    socket.recv(&mut buf)?;
-
-    S::from_bytes(&buf)
+    send_and_receive_with(request, d, &d.u.retry_policy)
+}
+
+/// Send a [`Request`] and receive a [`Response`], retrying according to `policy`.
+pub(crate) fn send_and_receive_with<T: messages::Request, S: messages::Response + Debug>(
+    request: T,
+    d: &Device,
+    policy: &RetryPolicy,
+) -> Result<S> {
+    let attempts = policy.attempts(request.get_message_type());
+    let timeout = d.timeout.or(policy.attempt_timeout).unwrap_or(d.u.timeout);
+    let mut attempt = 1;
+    loop {
+        match attempt_send_and_receive(&request, d, timeout) {
+            Err(e) if e.is_transient() && attempt < attempts => {
+                std::thread::sleep(policy.backoff(attempt));
+                attempt += 1;
+            }
+            Err(e) if attempt > 1 => {
+                return Err(Error::Retried {
+                    attempts: attempt,
+                    error: Box::new(e),
+                })
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Send a [`Request`] once and receive a [`Response`]. Datagrams that are not a reply to the
+/// request are discarded until `timeout` expires.
+fn attempt_send_and_receive<T: messages::Request, S: messages::Response + Debug>(
+    request: &T,
+    d: &Device,
+    timeout: Duration,
+) -> Result<S> {
+    let metrics = &d.u.metrics;
+    let message_type = request.get_message_type();
+    let transport = get_transport(d)?;
+    let sent = Instant::now();
+    metrics.record(message_type, |m| m.requests += 1);
+
+    let mut response = None;
+    let result = transport.exchange(&request.to_bytes(), Some(timeout), &mut |datagram| {
+        match decode_reply(request, datagram) {
+            Ok(Some(r)) => response = Some(r),
+            Ok(None) => {}
+            Err(_) => metrics.record(message_type, |m| m.decode_errors += 1),
+        }
+        response.is_some()
+    });
+    let result = result.and_then(|_| response.ok_or(Error::Timeout));
+
+    match &result {
+        Ok(_) => metrics.record(message_type, |m| {
+            m.responses += 1;
+            m.latency += sent.elapsed();
+        }),
+        Err(Error::Timeout) => metrics.record(message_type, |m| m.timeouts += 1),
+        Err(_) => {}
+    }
+    result
 }
 
 /// Send a [`Request`] to the [`Device`], but don't expect a response.
 fn send<T: messages::Request>(request: T, d: &Device) -> Result<()> {
-    let socket = setup_socket(d.u)?;
-    let addr = get_address(d);
-    socket.send_to(
-        &request.to_bytes(),
-        &SocketAddr::new(addr.into(), UHPPOTE_PORT),
-    )?;
+    get_transport(d)?.exchange(&request.to_bytes(), None, &mut |_| true)?;
+    d.u.metrics
+        .record(request.get_message_type(), |m| m.requests += 1);
     Ok(())
 }
 
+/// Check that a [`Card`] has permissions for exactly four doors and a PIN of at most 6 digits.
+pub(crate) fn check_card(card: &Card) -> Result<()> {
+    if card.doors.len() != 4 {
+        return Err(Error::InvalidArgument(format!(
+            "a card needs permissions for 4 doors, got {}",
+            card.doors.len()
+        )));
+    }
+    match card.pin {
+        Some(pin) if pin > 999999 => Err(Error::InvalidArgument(format!(
+            "PINs can be at most 6 digits, got {}",
+            pin
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Check that the delay of a [`DoorControl`] fits in a single byte.
+pub(crate) fn check_delay(state: &DoorControl) -> Result<()> {
+    if state.delay.as_secs() <= u8::MAX as u64 {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "door delay can be at most 255 seconds, got {}",
+            state.delay.as_secs()
+        )))
+    }
+}
+
+/// Check that `door` is one of doors 1-4.
+pub(crate) fn check_door(door: u8) -> Result<()> {
+    if (1..=4).contains(&door) {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "doors are addressed 1-4, got {}",
+            door
+        )))
+    }
+}
+
+/// Check that `door` is one of doors 1-4 and that all `passcodes` have at most 6 digits.
+pub(crate) fn check_passcodes(door: u8, passcodes: &[u32; 4]) -> Result<()> {
+    check_door(door)?;
+    match passcodes.iter().find(|&&p| p > 999999) {
+        Some(p) => Err(Error::InvalidArgument(format!(
+            "passcodes can be at most 6 digits, got {}",
+            p
+        ))),
+        None => Ok(()),
+    }
+}
+
 /// Get the IP address of the [`Device`]. If None, use the broadcast address from [`Uhppoted`]
 fn get_address(d: &Device) -> Ipv4Addr {
     match d.ip_address {
@@ -491,32 +937,42 @@ fn get_address(d: &Device) -> Ipv4Addr {
     }
 }
 
-/// Setup a socket with correct timeouts.
-fn setup_socket(u: &Uhppoted) -> Result<UdpSocket, anyhow::Error> {
-    let socket = UdpSocket::bind(u.bind_address)?;
-    socket.set_write_timeout(Some(Duration::new(1, 0)))?;
-    socket.set_read_timeout(Some(u.timeout))?;
-    socket.set_broadcast(true)?;
-    Ok(socket)
+/// Get the [`Transport`] for the [`Device`]: its own, else the one of the [`Uhppoted`], else UDP
+/// to the address of the [`Device`].
+fn get_transport(d: &Device) -> Result<Arc<dyn Transport>> {
+    match d.transport.as_ref().or(d.u.transport.as_ref()) {
+        Some(transport) => Ok(transport.clone()),
+        None => Ok(Arc::new(UdpTransport::with_dispatcher(
+            d.u.dispatcher()?,
+            SocketAddr::new(get_address(d).into(), d.port),
+        ))),
+    }
 }
 
-/// Broadcast a [`Request`] to all [`Device`]s.
+/// Broadcast a [`Request`] to all [`Device`]s and collect the replies that arrive within
+/// [`Uhppoted::timeout`].
 fn broadcast_and_receive<T: messages::Request, S: messages::Response + Debug>(
     request: T,
     u: &Uhppoted,
 ) -> Result<Vec<S>> {
-    let socket = setup_socket(u)?;
-
-    let to_addr = SocketAddr::new(u.broadcast_address.into(), UHPPOTE_PORT);
-
-    socket.send_to(&request.to_bytes(), to_addr)?;
-    let mut buf = [0u8; 64];
+    let message_type = request.get_message_type();
+    let transport: Arc<dyn Transport> = match &u.transport {
+        Some(transport) => transport.clone(),
+        None => Arc::new(UdpTransport::with_dispatcher(
+            u.dispatcher()?,
+            SocketAddr::new(u.broadcast_address.into(), UHPPOTE_PORT),
+        )),
+    };
+    u.metrics.record(message_type, |m| m.requests += 1);
 
     let mut ret = Vec::new();
-
-    while let Ok((_, _)) = socket.recv_from(&mut buf) {
-        ret.push(S::from_bytes(&buf)?);
-    }
+    transport.exchange(&request.to_bytes(), Some(u.timeout), &mut |datagram| {
+        match decode_reply(&request, datagram) {
+            Ok(response) => ret.extend(response),
+            Err(_) => u.metrics.record(message_type, |m| m.decode_errors += 1),
+        }
+        false
+    })?;
 
     Ok(ret)
 }
@@ -0,0 +1,253 @@
+use crate::messages::*;
+use crate::{Error, Result, Status};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often the listener thread checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A socket that receives [`Status`] messages sent by devices to their configured listener (see
+/// [`Device::set_listener`](crate::Device::set_listener)). Created with
+/// [`Uhppoted::listener`](crate::Uhppoted::listener).
+///
+/// Messages are delivered on a background thread, either to a closure ([`Listener::spawn`]) or
+/// into a channel ([`Listener::channel`]). Packets that can't be decoded are reported as an
+/// [`Error`] without stopping the listener.
+///
+/// Example:
+/// ```no_run
+/// use uhppote_rs::Uhppoted;
+/// let uhppoted = Uhppoted::default();
+/// let (handle, statuses) = uhppoted
+///     .listener("0.0.0.0:60001".parse().unwrap())
+///     .unwrap()
+///     .device(423196779)
+///     .channel()
+///     .unwrap();
+/// for status in statuses.iter().take(10) {
+///     println!("{:?}", status);
+/// }
+/// handle.stop();
+/// ```
+#[derive(Debug)]
+pub struct Listener {
+    socket: UdpSocket,
+    devices: Vec<u32>,
+}
+
+impl Listener {
+    pub(crate) fn bind(address: SocketAddr) -> Result<Listener> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_broadcast(true)?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Listener {
+            socket,
+            devices: Vec::new(),
+        })
+    }
+
+    /// Only deliver messages from the device with `id`. Can be called multiple times to accept
+    /// messages from several devices. By default, messages from all devices are delivered.
+    pub fn device(mut self, id: u32) -> Listener {
+        self.devices.push(id);
+        self
+    }
+
+    /// The address the [`Listener`] is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Start delivering messages to `handler` on a background thread, until the returned
+    /// [`ListenerHandle`] is stopped or dropped.
+    pub fn spawn<F>(self, mut handler: F) -> Result<ListenerHandle>
+    where
+        F: FnMut(Result<Status>) + Send + 'static,
+    {
+        let address = self.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if let Some(status) = self.receive() {
+                        handler(status);
+                    }
+                }
+            })
+        };
+        Ok(ListenerHandle {
+            address,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Start delivering messages into a channel on a background thread, until the returned
+    /// [`ListenerHandle`] is stopped or dropped. The [`Receiver`] is disconnected once the
+    /// listener has stopped.
+    pub fn channel(self) -> Result<(ListenerHandle, Receiver<Result<Status>>)> {
+        let (tx, rx) = mpsc::channel();
+        let handle = self.spawn(move |status| {
+            let _ = tx.send(status);
+        })?;
+        Ok((handle, rx))
+    }
+
+    /// Block and deliver messages to `handler` forever. Only returns when the socket fails.
+    pub(crate) fn run<F: FnMut(Status)>(self, mut handler: F) -> Result<()> {
+        loop {
+            match self.receive() {
+                Some(Ok(status)) => handler(status),
+                Some(Err(Error::Io(e))) => return Err(Error::Io(e)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Wait up to [`POLL_INTERVAL`] for a single message. Returns `None` when nothing (relevant)
+    /// was received.
+    fn receive(&self) -> Option<Result<Status>> {
+        // Larger than a message, so oversized datagrams aren't truncated into one.
+        let mut buf = [0u8; 1024];
+        match self.socket.recv(&mut buf) {
+            Ok(64) => self.decode(buf[..64].try_into().unwrap()),
+            Ok(n) => Some(Err(Error::Decode(format!(
+                "expected a 64 byte message, got {} bytes",
+                n
+            )))),
+            Err(e) => match Error::from(e) {
+                Error::Timeout => None,
+                e => Some(Err(e)),
+            },
+        }
+    }
+
+    fn decode(&self, buf: &[u8; 64]) -> Option<Result<Status>> {
+        decode_status(buf, &self.devices)
+    }
+}
+
+/// Decode a [`Status`] message, returning `None` if it is from a device not in `devices`. An
+/// empty `devices` accepts messages from all devices.
+pub(crate) fn decode_status(buf: &[u8; 64], devices: &[u32]) -> Option<Result<Status>> {
+    let response = match buf[1].try_into() {
+        Ok(RequestResponseType::Status) => GetStatusResponse::from_bytes(buf),
+        Ok(_) => Err(Error::UnexpectedMessageType(buf[1])),
+        Err(e) => Err(e),
+    };
+    match response {
+        Ok(r) if !devices.is_empty() && !devices.contains(&r.device_id) => None,
+        Ok(r) => Some(r.try_into()),
+        Err(e) => Some(Err(e)),
+    }
+}
+
+/// Handle to a running [`Listener`]. The listener is stopped when the handle is dropped.
+#[derive(Debug)]
+pub struct ListenerHandle {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ListenerHandle {
+    /// The address the [`Listener`] is bound to.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Stop the [`Listener`] and wait for its thread to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ListenerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: [u8; 64] = [
+        0x17, 0x20, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x39, 0x00, 0x00, 0x00, 0x01, 0x00, 0x03,
+        0x01, 0xaa, 0xe8, 0x5d, 0x00, 0x20, 0x19, 0x04, 0x19, 0x17, 0x00, 0x09, 0x06, 0x01, 0x00,
+        0x01, 0x01, 0x00, 0x00, 0x01, 0x01, 0x09, 0x14, 0x37, 0x02, 0x11, 0x00, 0x00, 0x00, 0x21,
+        0x00, 0x00, 0x00, 0x2b, 0x04, 0x01, 0x19, 0x04, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn listener() -> Listener {
+        Listener::bind("127.0.0.1:0".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn decode_status() {
+        let status = listener().decode(&STATUS).unwrap().unwrap();
+        assert_eq!(status.device_id, 423187757);
+        assert_eq!(status.last_event.unwrap().index, 57);
+    }
+
+    #[test]
+    fn decode_unexpected_message_type() {
+        let mut buf = STATUS;
+        buf[1] = 0x94;
+        let result = listener().decode(&buf).unwrap();
+        assert!(matches!(result, Err(Error::UnexpectedMessageType(0x94))));
+    }
+
+    #[test]
+    fn decode_filters_devices() {
+        assert!(listener().device(405419896).decode(&STATUS).is_none());
+        assert!(listener()
+            .device(405419896)
+            .device(423187757)
+            .decode(&STATUS)
+            .is_some());
+    }
+
+    #[test]
+    fn channel_survives_junk_and_stops() {
+        let (handle, rx) = listener().channel().unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.send_to(&[0u8; 10], handle.address()).unwrap();
+        socket.send_to(&STATUS, handle.address()).unwrap();
+
+        let timeout = Duration::from_secs(2);
+        assert!(matches!(
+            rx.recv_timeout(timeout),
+            Ok(Err(Error::Decode(_)))
+        ));
+        assert!(matches!(rx.recv_timeout(timeout), Ok(Ok(_))));
+
+        handle.stop();
+        assert!(rx.recv_timeout(timeout).is_err());
+    }
+
+    #[test]
+    fn oversized_datagrams_are_rejected() {
+        let listener = listener();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut oversized = STATUS.to_vec();
+        oversized.extend_from_slice(&[0u8; 16]);
+        socket
+            .send_to(&oversized, listener.local_addr().unwrap())
+            .unwrap();
+        assert!(matches!(listener.receive(), Some(Err(Error::Decode(_)))));
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Request, RequestResponseType, Response, HEADER};
+use bincode::{Decode, Encode};
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct ActivateKeypadsRequest {
+    header: u8,
+    message_type: u8,
+    _unused: u16,
+    device_id: u32,
+    pub reader_1: bool,
+    pub reader_2: bool,
+    pub reader_3: bool,
+    pub reader_4: bool,
+}
+
+impl ActivateKeypadsRequest {
+    pub fn new(
+        device_id: u32,
+        reader_1: bool,
+        reader_2: bool,
+        reader_3: bool,
+        reader_4: bool,
+    ) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::ActivateKeypads.into(),
+            _unused: 0,
+            device_id,
+            reader_1,
+            reader_2,
+            reader_3,
+            reader_4,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct ActivateKeypadsResponse {
+    pub header: u8,
+    pub message_type: u8,
+    _unused: u16,
+    pub device_id: u32,
+    pub success: bool,
+}
+
+#[cfg(feature = "simulator")]
+impl ActivateKeypadsResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::ActivateKeypads.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn activate_keypads_request_to_bytes() {
+        let expected = [
+            0x17, 0xa4, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x01, 0x01, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = ActivateKeypadsRequest::new(423187757, true, true, false, true);
+
+        let actual = r.to_bytes();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn activate_keypads_response_from_bytes() {
+        let bytes: [u8; 64] = [
+            0x17, 0xa4, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = ActivateKeypadsResponse::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::ActivateKeypads)
+        );
+        assert_eq!(r.device_id, 423187757);
+        assert!(r.success);
+    }
+}
@@ -2,25 +2,25 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use crate::messages::utils::types::{DateBCD, TimeWithoutSecondsBCD};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct AddTaskRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    from: DateBCD,
-    to: DateBCD,
-    monday: bool,
-    tuesday: bool,
-    wednesday: bool,
-    thursday: bool,
-    friday: bool,
-    saturday: bool,
-    sunday: bool,
-    at: TimeWithoutSecondsBCD,
-    door: u8,
-    task: u8,
-    more_cards: u8,
+    pub from: DateBCD,
+    pub to: DateBCD,
+    pub monday: bool,
+    pub tuesday: bool,
+    pub wednesday: bool,
+    pub thursday: bool,
+    pub friday: bool,
+    pub saturday: bool,
+    pub sunday: bool,
+    pub at: TimeWithoutSecondsBCD,
+    pub door: u8,
+    pub task: u8,
+    pub more_cards: u8,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -63,7 +63,7 @@ impl AddTaskRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct AddTaskResponse {
     pub header: u8,
     pub message_type: u8,
@@ -72,6 +72,19 @@ pub struct AddTaskResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl AddTaskResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::AddTask.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,7 +135,7 @@ mod tests {
         ];
 
         let r = AddTaskResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::AddTask.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::AddTask));
         assert_eq!(r.device_id, 423187757);
         assert!(r.success);
     }
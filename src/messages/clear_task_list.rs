@@ -1,13 +1,13 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct ClearTaskListRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    magic_word: u32,
+    pub magic_word: u32,
 }
 
 impl ClearTaskListRequest {
@@ -22,7 +22,7 @@ impl ClearTaskListRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct ClearTaskListResponse {
     pub header: u8,
     pub message_type: u8,
@@ -31,6 +31,19 @@ pub struct ClearTaskListResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl ClearTaskListResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::ClearTaskList.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,7 +74,7 @@ mod tests {
         ];
 
         let r = ClearTaskListResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::ClearTaskList.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::ClearTaskList));
         assert_eq!(r.device_id, 423187757);
         assert!(r.success);
     }
@@ -2,13 +2,13 @@ use bincode::{Decode, Encode};
 
 use super::{Request, RequestResponseType, Response, HEADER};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct ClearTimeProfilesRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    magic_word: u32,
+    pub magic_word: u32,
 }
 
 impl ClearTimeProfilesRequest {
@@ -23,7 +23,7 @@ impl ClearTimeProfilesRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct ClearTimeProfilesResponse {
     pub header: u8,
     pub message_type: u8,
@@ -32,6 +32,19 @@ pub struct ClearTimeProfilesResponse {
     pub magic_word: u32,
 }
 
+#[cfg(feature = "simulator")]
+impl ClearTimeProfilesResponse {
+    pub fn new(device_id: u32, magic_word: u32) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::ClearTimeProfiles.into(),
+            _unused: 0,
+            device_id,
+            magic_word,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,7 +77,7 @@ mod tests {
         let r = ClearTimeProfilesResponse::from_bytes(&bytes).unwrap();
         assert_eq!(
             r.message_type,
-            RequestResponseType::ClearTimeProfiles.into()
+            u8::from(RequestResponseType::ClearTimeProfiles)
         );
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.magic_word, 0x55aaaa55);
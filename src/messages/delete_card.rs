@@ -1,13 +1,13 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct DeleteCardRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    card_number: u32,
+    pub card_number: u32,
 }
 
 impl DeleteCardRequest {
@@ -22,7 +22,7 @@ impl DeleteCardRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct DeleteCardResponse {
     pub header: u8,
     pub message_type: u8,
@@ -31,6 +31,19 @@ pub struct DeleteCardResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl DeleteCardResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::DeleteCard.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[test]
 fn delete_card_response_from_bytes() {
     let bytes: [u8; 64] = [
@@ -42,7 +55,7 @@ fn delete_card_response_from_bytes() {
     ];
 
     let r = DeleteCardResponse::from_bytes(&bytes).unwrap();
-    assert_eq!(r.message_type, RequestResponseType::DeleteCard.into());
+    assert_eq!(r.message_type, u8::from(RequestResponseType::DeleteCard));
     assert_eq!(r.device_id, 423187757);
     assert!(r.success);
 }
@@ -77,7 +90,7 @@ mod tests {
         ];
 
         let r = DeleteCardResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::DeleteCard.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::DeleteCard));
         assert_eq!(r.device_id, 423187757);
         assert!(r.success);
     }
@@ -0,0 +1,82 @@
+use super::{Request, RequestResponseType, Response, HEADER};
+use bincode::{Decode, Encode};
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct GetAntiPassbackRequest {
+    header: u8,
+    message_type: u8,
+    _unused: u16,
+    device_id: u32,
+}
+
+impl GetAntiPassbackRequest {
+    pub fn new(device_id: u32) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetAntiPassback.into(),
+            _unused: 0,
+            device_id,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct GetAntiPassbackResponse {
+    pub header: u8,
+    pub message_type: u8,
+    _unused: u16,
+    pub device_id: u32,
+    pub antipassback: u8,
+}
+
+#[cfg(feature = "simulator")]
+impl GetAntiPassbackResponse {
+    pub fn new(device_id: u32, antipassback: u8) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetAntiPassback.into(),
+            _unused: 0,
+            device_id,
+            antipassback,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn get_antipassback_request_to_bytes() {
+        let expected = [
+            0x17, 0x86, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = GetAntiPassbackRequest::new(423187757);
+
+        let actual = r.to_bytes();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn get_antipassback_response_from_bytes() {
+        let bytes: [u8; 64] = [
+            0x17, 0x86, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = GetAntiPassbackResponse::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::GetAntiPassback)
+        );
+        assert_eq!(r.device_id, 423187757);
+        assert_eq!(r.antipassback, 2);
+    }
+}
@@ -1,13 +1,16 @@
-use super::{utils::types::DateBCD, Request, RequestResponseType, Response, HEADER};
+use super::{
+    utils::types::{DateBCD, Pin},
+    Request, RequestResponseType, Response, HEADER,
+};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetCardByIDRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    card_number: u32,
+    pub card_number: u32,
 }
 
 impl GetCardByIDRequest {
@@ -22,7 +25,7 @@ impl GetCardByIDRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetCardByIDResponse {
     pub header: u8,
     pub message_type: u8,
@@ -35,6 +38,38 @@ pub struct GetCardByIDResponse {
     pub door_2: u8,
     pub door_3: u8,
     pub door_4: u8,
+    pub pin: Pin,
+}
+
+#[cfg(feature = "simulator")]
+#[allow(clippy::too_many_arguments)]
+impl GetCardByIDResponse {
+    pub fn new(
+        device_id: u32,
+        card_number: u32,
+        from: DateBCD,
+        to: DateBCD,
+        door_1: u8,
+        door_2: u8,
+        door_3: u8,
+        door_4: u8,
+        pin: Pin,
+    ) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetCardByID.into(),
+            _unused: 0,
+            device_id,
+            card_number,
+            from,
+            to,
+            door_1,
+            door_2,
+            door_3,
+            door_4,
+            pin,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -67,7 +102,7 @@ mod tests {
         ];
 
         let r = GetCardByIDResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetCardByID.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetCardByID));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.card_number, 6154412);
         assert_eq!(r.from, DateBCD::new(2019, 2, 3));
@@ -76,5 +111,21 @@ mod tests {
         assert_eq!(r.door_2, 0);
         assert_eq!(r.door_3, 29);
         assert_eq!(r.door_4, 1);
+        assert_eq!(r.pin, Pin::default());
+    }
+
+    #[test]
+    fn get_card_by_id_response_with_pin_from_bytes() {
+        let bytes: [u8; 64] = [
+            0x17, 0x5a, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0xac, 0xe8, 0x5d, 0x00, 0x20, 0x19,
+            0x02, 0x03, 0x20, 0x19, 0x12, 0x29, 0x00, 0x00, 0x1d, 0x01, 0x39, 0x30, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = GetCardByIDResponse::from_bytes(&bytes).unwrap();
+        assert_eq!(r.card_number, 6154412);
+        assert_eq!(r.pin, Pin::new(12345));
     }
 }
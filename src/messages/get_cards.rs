@@ -1,7 +1,7 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetCardsRequest {
     header: u8,
     message_type: u8,
@@ -20,7 +20,7 @@ impl GetCardsRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetCardsResponse {
     pub header: u8,
     pub message_type: u8,
@@ -29,6 +29,19 @@ pub struct GetCardsResponse {
     pub records: u32,
 }
 
+#[cfg(feature = "simulator")]
+impl GetCardsResponse {
+    pub fn new(device_id: u32, records: u32) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetCards.into(),
+            _unused: 0,
+            device_id,
+            records,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,7 +73,7 @@ mod tests {
         ];
 
         let r = GetCardsResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetCards.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetCards));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.records, 13);
     }
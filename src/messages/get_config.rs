@@ -6,7 +6,7 @@ use crate::messages::utils::types::DateBCD;
 use bincode::{Decode, Encode};
 use std::net::Ipv4Addr;
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetConfigRequest {
     header: u8,
     message_type: u8,
@@ -25,7 +25,7 @@ impl GetConfigRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetConfigResponse {
     pub header: u8,
     pub message_type: u8,
@@ -39,6 +39,32 @@ pub struct GetConfigResponse {
     pub date: DateBCD,
 }
 
+#[cfg(feature = "simulator")]
+impl GetConfigResponse {
+    pub fn new(
+        device_id: u32,
+        ip_address: Ipv4Addr,
+        subnet: Ipv4Addr,
+        gateway: Ipv4Addr,
+        mac: MacAddress,
+        version: Version,
+        date: DateBCD,
+    ) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetConfig.into(),
+            _unused: 0,
+            device_id,
+            ip_address,
+            subnet,
+            gateway,
+            mac,
+            version,
+            date,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,7 +95,7 @@ mod tests {
         ];
 
         let r = GetConfigResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetConfig.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetConfig));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.ip_address, Ipv4Addr::new(192, 168, 0, 0));
         assert_eq!(r.subnet, Ipv4Addr::new(255, 255, 255, 0));
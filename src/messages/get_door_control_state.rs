@@ -1,13 +1,13 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetDoorControlStateRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    door: u8,
+    pub door: u8,
 }
 
 impl GetDoorControlStateRequest {
@@ -22,7 +22,7 @@ impl GetDoorControlStateRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetDoorControlStateResponse {
     pub header: u8,
     pub message_type: u8,
@@ -33,6 +33,21 @@ pub struct GetDoorControlStateResponse {
     pub delay: u8,
 }
 
+#[cfg(feature = "simulator")]
+impl GetDoorControlStateResponse {
+    pub fn new(device_id: u32, door: u8, control_state: u8, delay: u8) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetDoorControlState.into(),
+            _unused: 0,
+            device_id,
+            door,
+            control_state,
+            delay,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,7 +80,7 @@ mod tests {
         let r = GetDoorControlStateResponse::from_bytes(&bytes).unwrap();
         assert_eq!(
             r.message_type,
-            RequestResponseType::GetDoorControlState.into()
+            u8::from(RequestResponseType::GetDoorControlState)
         );
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.door, 4);
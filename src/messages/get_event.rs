@@ -1,13 +1,13 @@
 use super::{utils::types::DateTime, Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetEventRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    index: u32,
+    pub index: u32,
 }
 
 impl GetEventRequest {
@@ -22,7 +22,7 @@ impl GetEventRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetEventResponse {
     pub header: u8,
     pub message_type: u8,
@@ -38,6 +38,37 @@ pub struct GetEventResponse {
     pub reason: u8,
 }
 
+#[cfg(feature = "simulator")]
+#[allow(clippy::too_many_arguments)]
+impl GetEventResponse {
+    pub fn new(
+        device_id: u32,
+        index: u32,
+        type_: u8,
+        granted: bool,
+        door: u8,
+        direction: u8,
+        card_number: u32,
+        timestamp: DateTime,
+        reason: u8,
+    ) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetEvent.into(),
+            _unused: 0,
+            device_id,
+            index,
+            type_,
+            granted,
+            door,
+            direction,
+            card_number,
+            timestamp,
+            reason,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,7 +99,7 @@ mod tests {
         ];
 
         let r = GetEventResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetEvent.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetEvent));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.index, 8);
         assert_eq!(r.type_, 2);
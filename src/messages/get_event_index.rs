@@ -1,7 +1,7 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetEventIndexRequest {
     header: u8,
     message_type: u8,
@@ -20,7 +20,7 @@ impl GetEventIndexRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetEventIndexResponse {
     pub header: u8,
     pub message_type: u8,
@@ -29,6 +29,19 @@ pub struct GetEventIndexResponse {
     pub index: u32,
 }
 
+#[cfg(feature = "simulator")]
+impl GetEventIndexResponse {
+    pub fn new(device_id: u32, index: u32) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetEventIndex.into(),
+            _unused: 0,
+            device_id,
+            index,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,7 +72,7 @@ mod tests {
         ];
 
         let r = GetEventIndexResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetEventIndex.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetEventIndex));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.index, 17);
     }
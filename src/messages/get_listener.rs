@@ -2,7 +2,7 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 use std::net::Ipv4Addr;
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetListenerRequest {
     header: u8,
     message_type: u8,
@@ -21,7 +21,7 @@ impl GetListenerRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetListenerResponse {
     pub header: u8,
     pub message_type: u8,
@@ -31,6 +31,20 @@ pub struct GetListenerResponse {
     pub port: u16,
 }
 
+#[cfg(feature = "simulator")]
+impl GetListenerResponse {
+    pub fn new(device_id: u32, ip_address: Ipv4Addr, port: u16) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetListener.into(),
+            _unused: 0,
+            device_id,
+            ip_address,
+            port,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,7 +74,7 @@ mod tests {
         ];
 
         let r = GetListenerResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetListener.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetListener));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.ip_address, Ipv4Addr::new(192, 168, 0, 225));
         assert_eq!(r.port, 9874);
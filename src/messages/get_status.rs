@@ -2,7 +2,7 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use crate::messages::utils::types::{DateShortBCD, DateTime, TimeWithSecondsBCD};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetStatusRequest {
     header: u8,
     message_type: u8,
@@ -185,7 +185,7 @@ mod tests {
         ];
 
         let r = GetStatusResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::Status.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::Status));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.event_index, 57);
         assert_eq!(r.event_type, 1);
@@ -2,8 +2,7 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use crate::messages::utils::types::DateTime;
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
-
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetTimeRequest {
     header: u8,
     message_type: u8,
@@ -22,7 +21,7 @@ impl GetTimeRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetTimeResponse {
     pub header: u8,
     pub message_type: u8,
@@ -31,6 +30,19 @@ pub struct GetTimeResponse {
     pub datetime: DateTime,
 }
 
+#[cfg(feature = "simulator")]
+impl GetTimeResponse {
+    pub fn new(device_id: u32, datetime: DateTime) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetTime.into(),
+            _unused: 0,
+            device_id,
+            datetime,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,7 +73,7 @@ mod tests {
         ];
 
         let r = GetTimeResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetTime.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetTime));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.datetime, DateTime::new(2019, 12, 29, 12, 34, 56));
     }
@@ -2,13 +2,13 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use crate::messages::utils::types::{DateBCD, TimeWithoutSecondsBCD};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetTimeProfileRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    profile_id: u8,
+    pub profile_id: u8,
 }
 
 impl GetTimeProfileRequest {
@@ -23,7 +23,7 @@ impl GetTimeProfileRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct GetTimeProfileResponse {
     pub header: u8,
     pub message_type: u8,
@@ -48,6 +48,55 @@ pub struct GetTimeProfileResponse {
     pub linked_profile_id: u8,
 }
 
+#[cfg(feature = "simulator")]
+#[allow(clippy::too_many_arguments)]
+impl GetTimeProfileResponse {
+    pub fn new(
+        device_id: u32,
+        profile_id: u8,
+        from: DateBCD,
+        to: DateBCD,
+        monday: bool,
+        tuesday: bool,
+        wednesday: bool,
+        thursday: bool,
+        friday: bool,
+        saturday: bool,
+        sunday: bool,
+        segment1_start: TimeWithoutSecondsBCD,
+        segment1_end: TimeWithoutSecondsBCD,
+        segment2_start: TimeWithoutSecondsBCD,
+        segment2_end: TimeWithoutSecondsBCD,
+        segment3_start: TimeWithoutSecondsBCD,
+        segment3_end: TimeWithoutSecondsBCD,
+        linked_profile_id: u8,
+    ) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::GetTimeProfile.into(),
+            _unused: 0,
+            device_id,
+            profile_id,
+            from,
+            to,
+            monday,
+            tuesday,
+            wednesday,
+            thursday,
+            friday,
+            saturday,
+            sunday,
+            segment1_start,
+            segment1_end,
+            segment2_start,
+            segment2_end,
+            segment3_start,
+            segment3_end,
+            linked_profile_id,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,7 +127,10 @@ mod tests {
         ];
 
         let r = GetTimeProfileResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetTimeProfile.into());
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::GetTimeProfile)
+        );
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.profile_id, 4);
         assert_eq!(r.from, DateBCD::new(2021, 4, 1));
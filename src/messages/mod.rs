@@ -1,8 +1,10 @@
+mod activate_keypads;
 mod add_task;
 mod clear_task_list;
 mod clear_time_profiles;
 mod delete_card;
 mod delete_cards;
+mod get_antipassback;
 mod get_card_by_id;
 mod get_card_by_index;
 mod get_cards;
@@ -18,23 +20,28 @@ mod open_door;
 mod put_card;
 mod refresh_task_list;
 mod set_address;
+mod set_antipassback;
 mod set_door_control_state;
+mod set_door_passcodes;
 mod set_event_index;
-// mod set_first_card;
+mod set_first_card;
+mod set_interlock;
 mod set_listener;
 mod set_record_special_events;
 mod set_time;
 mod set_time_profile;
 mod utils;
 
+pub use self::utils::request::decode_reply;
 pub use self::utils::request::Request;
 pub use self::utils::request::Response;
+pub use activate_keypads::*;
 pub use add_task::*;
-use anyhow::bail;
 pub use clear_task_list::*;
 pub use clear_time_profiles::*;
 pub use delete_card::*;
 pub use delete_cards::*;
+pub use get_antipassback::*;
 pub use get_card_by_id::*;
 pub use get_card_by_index::*;
 pub use get_cards::*;
@@ -50,9 +57,12 @@ pub use open_door::*;
 pub use put_card::*;
 pub use refresh_task_list::*;
 pub use set_address::*;
+pub use set_antipassback::*;
 pub use set_door_control_state::*;
+pub use set_door_passcodes::*;
 pub use set_event_index::*;
-// pub use set_first_card::*;
+pub use set_first_card::*;
+pub use set_interlock::*;
 pub use set_listener::*;
 pub use set_record_special_events::*;
 pub use set_time::*;
@@ -76,15 +86,20 @@ pub enum RequestResponseType {
     GetCardByIndex = 0x5c,
     SetDoorControlState = 0x80,
     GetDoorControlState = 0x82,
+    SetAntiPassback = 0x84,
+    GetAntiPassback = 0x86,
     SetTimeProfile = 0x88,
+    SetDoorPasscodes = 0x8c,
     SetListener = 0x90,
     GetListener = 0x92,
     GetConfig = 0x94,
     SetAddress = 0x96,
     GetTimeProfile = 0x98,
+    SetInterlock = 0xa2,
+    ActivateKeypads = 0xa4,
     ClearTaskList = 0xa6,
     AddTask = 0xa8,
-    // SetFirstCard = 0xaa,
+    SetFirstCard = 0xaa,
     RefreshTaskList = 0xac,
     GetEvent = 0xb0,
     SetEventIndex = 0xb2,
@@ -100,8 +115,8 @@ impl From<RequestResponseType> for u8 {
 }
 
 impl TryFrom<u8> for RequestResponseType {
-    type Error = anyhow::Error;
-    fn try_from(value: u8) -> anyhow::Result<Self> {
+    type Error = crate::Error;
+    fn try_from(value: u8) -> crate::Result<Self> {
         match value {
             0x20 => Ok(RequestResponseType::Status),
             0x30 => Ok(RequestResponseType::SetTime),
@@ -115,22 +130,27 @@ impl TryFrom<u8> for RequestResponseType {
             0x5c => Ok(RequestResponseType::GetCardByIndex),
             0x80 => Ok(RequestResponseType::SetDoorControlState),
             0x82 => Ok(RequestResponseType::GetDoorControlState),
+            0x84 => Ok(RequestResponseType::SetAntiPassback),
+            0x86 => Ok(RequestResponseType::GetAntiPassback),
             0x88 => Ok(RequestResponseType::SetTimeProfile),
+            0x8c => Ok(RequestResponseType::SetDoorPasscodes),
             0x90 => Ok(RequestResponseType::SetListener),
             0x92 => Ok(RequestResponseType::GetListener),
             0x94 => Ok(RequestResponseType::GetConfig),
             0x96 => Ok(RequestResponseType::SetAddress),
             0x98 => Ok(RequestResponseType::GetTimeProfile),
+            0xa2 => Ok(RequestResponseType::SetInterlock),
+            0xa4 => Ok(RequestResponseType::ActivateKeypads),
             0xa6 => Ok(RequestResponseType::ClearTaskList),
             0xa8 => Ok(RequestResponseType::AddTask),
-            // 0xaa => Ok(RequestResponseType::SetFirstCard),
+            0xaa => Ok(RequestResponseType::SetFirstCard),
             0xac => Ok(RequestResponseType::RefreshTaskList),
             0xb0 => Ok(RequestResponseType::GetEvent),
             0xb2 => Ok(RequestResponseType::SetEventIndex),
             0xb4 => Ok(RequestResponseType::GetEventIndex),
             0x8a => Ok(RequestResponseType::ClearTimeProfiles),
             0x8e => Ok(RequestResponseType::SetRecordSpecialEvents),
-            _ => bail!("Invalid request type: {:x}", value),
+            _ => Err(crate::Error::UnexpectedMessageType(value)),
         }
     }
 }
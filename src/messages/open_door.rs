@@ -1,13 +1,13 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct OpenDoorRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    door_id: u8,
+    pub door_id: u8,
 }
 
 impl OpenDoorRequest {
@@ -38,7 +38,7 @@ fn open_door_request_to_bytes() {
     assert_eq!(expected, actual);
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct OpenDoorResponse {
     pub header: u8,
     pub message_type: u8,
@@ -47,6 +47,19 @@ pub struct OpenDoorResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl OpenDoorResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::OpenDoor.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,7 +74,7 @@ mod tests {
         ];
 
         let r = OpenDoorResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::OpenDoor.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::OpenDoor));
         assert_eq!(r.device_id, 423187757);
         assert!(r.success);
     }
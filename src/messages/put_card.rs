@@ -1,20 +1,23 @@
-use super::{utils::types::DateBCD, Request, RequestResponseType, Response, HEADER};
+use super::{
+    utils::types::{DateBCD, Pin},
+    Request, RequestResponseType, Response, HEADER,
+};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
-
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct PutCardRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    card_id: u32,
-    from: DateBCD,
-    to: DateBCD,
-    door_1: u8,
-    door_2: u8,
-    door_3: u8,
-    door_4: u8,
+    pub card_id: u32,
+    pub from: DateBCD,
+    pub to: DateBCD,
+    pub door_1: u8,
+    pub door_2: u8,
+    pub door_3: u8,
+    pub door_4: u8,
+    pub pin: Pin,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -28,6 +31,7 @@ impl PutCardRequest {
         door_2: u8,
         door_3: u8,
         door_4: u8,
+        pin: Pin,
     ) -> Self {
         PutCardRequest {
             header: HEADER,
@@ -41,11 +45,12 @@ impl PutCardRequest {
             door_2,
             door_3,
             door_4,
+            pin,
         }
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct PutCardResponse {
     pub header: u8,
     pub message_type: u8,
@@ -54,6 +59,19 @@ pub struct PutCardResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl PutCardResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::PutCard.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,6 +94,33 @@ mod tests {
             0,
             29,
             1,
+            Pin::default(),
+        );
+
+        let actual = r.to_bytes();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn put_card_request_with_pin_to_bytes() {
+        let expected = [
+            0x17, 0x50, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0xac, 0xe8, 0x5d, 0x00, 0x20, 0x19,
+            0x01, 0x02, 0x20, 0x19, 0x12, 0x31, 0x01, 0x00, 0x1d, 0x01, 0x3f, 0x42, 0x0f, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = PutCardRequest::new(
+            423187757,
+            6154412,
+            DateBCD::new(2019, 1, 2),
+            DateBCD::new(2019, 12, 31),
+            1,
+            0,
+            29,
+            1,
+            Pin::new(999999),
         );
 
         let actual = r.to_bytes();
@@ -93,7 +138,7 @@ mod tests {
         ];
 
         let r = PutCardResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::PutCard.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::PutCard));
         assert!(r.success);
     }
 }
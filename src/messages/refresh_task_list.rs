@@ -1,13 +1,13 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct RefreshTaskListRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    magic_word: u32,
+    pub magic_word: u32,
 }
 
 impl RefreshTaskListRequest {
@@ -22,7 +22,7 @@ impl RefreshTaskListRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct RefreshTaskListResponse {
     pub header: u8,
     pub message_type: u8,
@@ -31,6 +31,19 @@ pub struct RefreshTaskListResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl RefreshTaskListResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::RefreshTaskList.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,7 +74,10 @@ mod tests {
         ];
 
         let r = RefreshTaskListResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::RefreshTaskList.into());
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::RefreshTaskList)
+        );
         assert!(r.success);
     }
 }
@@ -1,17 +1,17 @@
-use super::{Request, RequestResponseType, HEADER};
-use bincode::Encode;
+use super::{Request, RequestResponseType, Response, HEADER};
+use bincode::{Decode, Encode};
 use std::net::Ipv4Addr;
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetAddressRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    ip_address: Ipv4Addr,
-    subnet: Ipv4Addr,
-    gateway: Ipv4Addr,
-    magic_word: u32,
+    pub ip_address: Ipv4Addr,
+    pub subnet: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub magic_word: u32,
 }
 
 impl SetAddressRequest {
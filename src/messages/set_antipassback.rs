@@ -0,0 +1,84 @@
+use super::{Request, RequestResponseType, Response, HEADER};
+use bincode::{Decode, Encode};
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct SetAntiPassbackRequest {
+    header: u8,
+    message_type: u8,
+    _unused: u16,
+    device_id: u32,
+    pub antipassback: u8,
+}
+
+impl SetAntiPassbackRequest {
+    pub fn new(device_id: u32, antipassback: u8) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetAntiPassback.into(),
+            _unused: 0,
+            device_id,
+            antipassback,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct SetAntiPassbackResponse {
+    pub header: u8,
+    pub message_type: u8,
+    _unused: u16,
+    pub device_id: u32,
+    pub success: bool,
+}
+
+#[cfg(feature = "simulator")]
+impl SetAntiPassbackResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetAntiPassback.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn set_antipassback_request_to_bytes() {
+        let expected = [
+            0x17, 0x84, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = SetAntiPassbackRequest::new(423187757, 2);
+
+        let actual = r.to_bytes();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn set_antipassback_response_from_bytes() {
+        let bytes: [u8; 64] = [
+            0x17, 0x84, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = SetAntiPassbackResponse::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::SetAntiPassback)
+        );
+        assert_eq!(r.device_id, 423187757);
+        assert!(r.success);
+    }
+}
@@ -1,15 +1,15 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetDoorControlStateRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    door: u8,
-    control_state: u8,
-    delay: u8,
+    pub door: u8,
+    pub control_state: u8,
+    pub delay: u8,
 }
 
 impl SetDoorControlStateRequest {
@@ -26,7 +26,7 @@ impl SetDoorControlStateRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetDoorControlStateResponse {
     pub header: u8,
     pub message_type: u8,
@@ -37,6 +37,21 @@ pub struct SetDoorControlStateResponse {
     pub delay: u8,
 }
 
+#[cfg(feature = "simulator")]
+impl SetDoorControlStateResponse {
+    pub fn new(device_id: u32, door: u8, control_state: u8, delay: u8) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetDoorControlState.into(),
+            _unused: 0,
+            device_id,
+            door,
+            control_state,
+            delay,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,7 +84,7 @@ mod tests {
         let r = SetDoorControlStateResponse::from_bytes(&bytes).unwrap();
         assert_eq!(
             r.message_type,
-            RequestResponseType::SetDoorControlState.into()
+            u8::from(RequestResponseType::SetDoorControlState)
         );
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.door, 4);
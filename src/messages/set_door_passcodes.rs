@@ -0,0 +1,103 @@
+use super::{Request, RequestResponseType, Response, HEADER};
+use bincode::{Decode, Encode};
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct SetDoorPasscodesRequest {
+    header: u8,
+    message_type: u8,
+    _unused: u16,
+    device_id: u32,
+    pub door: u8,
+    _unused2: u8,
+    _unused3: u16,
+    pub passcode_1: u32,
+    pub passcode_2: u32,
+    pub passcode_3: u32,
+    pub passcode_4: u32,
+}
+
+impl SetDoorPasscodesRequest {
+    pub fn new(
+        device_id: u32,
+        door: u8,
+        passcode_1: u32,
+        passcode_2: u32,
+        passcode_3: u32,
+        passcode_4: u32,
+    ) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetDoorPasscodes.into(),
+            _unused: 0,
+            device_id,
+            door,
+            _unused2: 0,
+            _unused3: 0,
+            passcode_1,
+            passcode_2,
+            passcode_3,
+            passcode_4,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Request, Response, Debug)]
+pub struct SetDoorPasscodesResponse {
+    pub header: u8,
+    pub message_type: u8,
+    _unused: u16,
+    pub device_id: u32,
+    pub success: bool,
+}
+
+#[cfg(feature = "simulator")]
+impl SetDoorPasscodesResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetDoorPasscodes.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn set_door_passcodes_request_to_bytes() {
+        let expected = [
+            0x17, 0x8c, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x03, 0x00, 0x00, 0x00, 0x39, 0x30,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3f, 0x42, 0x0f, 0x00, 0x31, 0xd4, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = SetDoorPasscodesRequest::new(423187757, 3, 12345, 0, 999999, 54321);
+
+        let actual = r.to_bytes();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn set_door_passcodes_response_from_bytes() {
+        let bytes: [u8; 64] = [
+            0x17, 0x8c, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let r = SetDoorPasscodesResponse::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::SetDoorPasscodes)
+        );
+        assert_eq!(r.device_id, 423187757);
+        assert!(r.success);
+    }
+}
@@ -1,14 +1,14 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetEventIndexRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    index: u32,
-    magic_word: u32,
+    pub index: u32,
+    pub magic_word: u32,
 }
 
 impl SetEventIndexRequest {
@@ -24,7 +24,7 @@ impl SetEventIndexRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetEventIndexResponse {
     pub header: u8,
     pub message_type: u8,
@@ -33,6 +33,19 @@ pub struct SetEventIndexResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl SetEventIndexResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetEventIndex.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,7 +76,7 @@ mod tests {
         ];
 
         let r = SetEventIndexResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::SetEventIndex.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::SetEventIndex));
         assert!(r.success);
     }
 }
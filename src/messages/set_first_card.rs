@@ -1,24 +1,24 @@
 use super::{utils::types::TimeWithoutSecondsBCD, Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetFirstCardRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    door: u8,
-    start: TimeWithoutSecondsBCD,
-    start_door_control: u8,
-    end: TimeWithoutSecondsBCD,
-    end_door_control: u8,
-    monday: bool,
-    tuesday: bool,
-    wednesday: bool,
-    thursday: bool,
-    friday: bool,
-    saturday: bool,
-    sunday: bool,
+    pub door: u8,
+    pub start: TimeWithoutSecondsBCD,
+    pub start_door_control: u8,
+    pub end: TimeWithoutSecondsBCD,
+    pub end_door_control: u8,
+    pub monday: bool,
+    pub tuesday: bool,
+    pub wednesday: bool,
+    pub thursday: bool,
+    pub friday: bool,
+    pub saturday: bool,
+    pub sunday: bool,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -59,7 +59,7 @@ impl SetFirstCardRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetFirstCardResponse {
     pub header: u8,
     pub message_type: u8,
@@ -68,11 +68,24 @@ pub struct SetFirstCardResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl SetFirstCardResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetFirstCard.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
-    fn set_listener_request_to_bytes() {
+    fn set_first_card_request_to_bytes() {
         let expected = [
             0x17, 0xaa, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x03, 0x08, 0x30, 0x01, 0x17, 0x45,
             0x02, 0x01, 0x01, 0x00, 0x01, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -102,7 +115,7 @@ mod tests {
     }
 
     #[test]
-    fn set_listener_response_from_bytes() {
+    fn set_first_card_response_from_bytes() {
         let bytes: [u8; 64] = [
             0x17, 0xaa, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -112,7 +125,8 @@ mod tests {
         ];
 
         let r = SetFirstCardResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::SetFirstCard.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::SetFirstCard));
+        assert_eq!(r.device_id, 423187757);
         assert!(r.success);
     }
 }
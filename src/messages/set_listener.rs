@@ -2,14 +2,14 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 use std::net::Ipv4Addr;
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetListenerRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    ip_address: Ipv4Addr,
-    port: u16,
+    pub ip_address: Ipv4Addr,
+    pub port: u16,
 }
 
 impl SetListenerRequest {
@@ -41,7 +41,7 @@ fn set_listener_request_to_bytes() {
     assert_eq!(expected, actual);
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetListenerResponse {
     pub header: u8,
     pub message_type: u8,
@@ -50,6 +50,19 @@ pub struct SetListenerResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl SetListenerResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetListener.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,7 +93,7 @@ mod tests {
         ];
 
         let r = SetListenerResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::SetListener.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::SetListener));
         assert!(r.success);
     }
 }
@@ -1,13 +1,13 @@
 use super::{Request, RequestResponseType, Response, HEADER};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetRecordSpecialEventsRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    enable: bool,
+    pub enable: bool,
 }
 
 impl SetRecordSpecialEventsRequest {
@@ -22,7 +22,7 @@ impl SetRecordSpecialEventsRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetRecordSpecialEventsResponse {
     pub header: u8,
     pub message_type: u8,
@@ -31,6 +31,19 @@ pub struct SetRecordSpecialEventsResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl SetRecordSpecialEventsResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetRecordSpecialEvents.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,7 +76,7 @@ mod tests {
         let r = SetRecordSpecialEventsResponse::from_bytes(&bytes).unwrap();
         assert_eq!(
             r.message_type,
-            RequestResponseType::SetRecordSpecialEvents.into()
+            u8::from(RequestResponseType::SetRecordSpecialEvents)
         );
         assert!(r.success);
     }
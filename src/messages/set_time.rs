@@ -2,14 +2,13 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use crate::messages::utils::types::DateTime;
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
-
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetTimeRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    datetime: DateTime,
+    pub datetime: DateTime,
 }
 
 impl SetTimeRequest {
@@ -24,7 +23,7 @@ impl SetTimeRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetTimeResponse {
     pub header: u8,
     pub message_type: u8,
@@ -33,6 +32,19 @@ pub struct SetTimeResponse {
     pub datetime: DateTime,
 }
 
+#[cfg(feature = "simulator")]
+impl SetTimeResponse {
+    pub fn new(device_id: u32, datetime: DateTime) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetTime.into(),
+            _unused: 0,
+            device_id,
+            datetime,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,7 +75,7 @@ mod tests {
         ];
 
         let r = SetTimeResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::GetTime.into());
+        assert_eq!(r.message_type, u8::from(RequestResponseType::GetTime));
         assert_eq!(r.device_id, 423187757);
         assert_eq!(r.datetime, DateTime::new(2019, 12, 29, 12, 34, 56));
     }
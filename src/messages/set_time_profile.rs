@@ -2,29 +2,29 @@ use super::{Request, RequestResponseType, Response, HEADER};
 use crate::messages::utils::types::{DateBCD, TimeWithoutSecondsBCD};
 use bincode::{Decode, Encode};
 
-#[derive(Encode, Request)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetTimeProfileRequest {
     header: u8,
     message_type: u8,
     _unused: u16,
     device_id: u32,
-    profile_id: u8,
-    from: DateBCD,
-    to: DateBCD,
-    monday: bool,
-    tuesday: bool,
-    wednesday: bool,
-    thursday: bool,
-    friday: bool,
-    saturday: bool,
-    sunday: bool,
-    segment1_start: TimeWithoutSecondsBCD,
-    segment1_end: TimeWithoutSecondsBCD,
-    segment2_start: TimeWithoutSecondsBCD,
-    segment2_end: TimeWithoutSecondsBCD,
-    segment3_start: TimeWithoutSecondsBCD,
-    segment3_end: TimeWithoutSecondsBCD,
-    linked_profile_id: u8,
+    pub profile_id: u8,
+    pub from: DateBCD,
+    pub to: DateBCD,
+    pub monday: bool,
+    pub tuesday: bool,
+    pub wednesday: bool,
+    pub thursday: bool,
+    pub friday: bool,
+    pub saturday: bool,
+    pub sunday: bool,
+    pub segment1_start: TimeWithoutSecondsBCD,
+    pub segment1_end: TimeWithoutSecondsBCD,
+    pub segment2_start: TimeWithoutSecondsBCD,
+    pub segment2_end: TimeWithoutSecondsBCD,
+    pub segment3_start: TimeWithoutSecondsBCD,
+    pub segment3_end: TimeWithoutSecondsBCD,
+    pub linked_profile_id: u8,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -75,7 +75,7 @@ impl SetTimeProfileRequest {
     }
 }
 
-#[derive(Decode, Response, Debug)]
+#[derive(Encode, Decode, Request, Response, Debug)]
 pub struct SetTimeProfileResponse {
     pub header: u8,
     pub message_type: u8,
@@ -84,6 +84,19 @@ pub struct SetTimeProfileResponse {
     pub success: bool,
 }
 
+#[cfg(feature = "simulator")]
+impl SetTimeProfileResponse {
+    pub fn new(device_id: u32, success: bool) -> Self {
+        Self {
+            header: HEADER,
+            message_type: RequestResponseType::SetTimeProfile.into(),
+            _unused: 0,
+            device_id,
+            success,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,7 +147,10 @@ mod tests {
         ];
 
         let r = SetTimeProfileResponse::from_bytes(&bytes).unwrap();
-        assert_eq!(r.message_type, RequestResponseType::SetTimeProfile.into());
+        assert_eq!(
+            r.message_type,
+            u8::from(RequestResponseType::SetTimeProfile)
+        );
         assert_eq!(r.device_id, 423187757);
         assert!(r.success);
     }
@@ -1,9 +1,8 @@
 pub fn encode(s: String) -> Vec<u8> {
-    let n: usize = (s.len() + 1) / 2;
+    let n: usize = s.len().div_ceil(2);
     let mut buf = vec![0; n];
-    let mut ix = s.len() % 2;
 
-    for c in s.chars() {
+    for (ix, c) in (s.len() % 2..).zip(s.chars()) {
         let b = match c {
             '0' => 0x0,
             '1' => 0x1,
@@ -19,7 +18,6 @@ pub fn encode(s: String) -> Vec<u8> {
         };
         buf[ix / 2] = ((buf[ix / 2] as u16 * 16) % 255) as u8;
         buf[ix / 2] = ((buf[ix / 2] as u16 + b as u16) % 255) as u8;
-        ix += 1;
     }
     buf
 }
@@ -29,7 +27,7 @@ mod tests {
     use super::*;
     #[test]
     fn test_encode() {
-        assert_eq!(encode("".to_string()), vec![]);
+        assert_eq!(encode("".to_string()), Vec::<u8>::new());
         assert_eq!(encode("1".to_string()), vec![0x01]);
         assert_eq!(encode("12".to_string()), vec![0x12]);
         assert_eq!(encode("123".to_string()), vec![0x01, 0x23]);
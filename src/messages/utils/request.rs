@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use anyhow::Result;
+use crate::{Error, Result};
 pub trait Request {
     fn to_bytes(&self) -> [u8; 64];
     fn to_bytes_impl(&self) -> [u8; 64]
@@ -18,6 +18,7 @@ pub trait Request {
         result
     }
     fn get_id(&self) -> u32;
+    fn get_message_type(&self) -> u8;
 }
 
 pub trait Response {
@@ -35,9 +36,107 @@ pub trait Response {
             .with_fixed_int_encoding()
             .with_little_endian();
 
-        match bincode::decode_from_slice(bytes, options) {
-            Ok((res, _)) => Ok(res),
-            Err(e) => Err(anyhow::Error::from(e)),
-        }
+        let (res, _) = bincode::decode_from_slice(bytes, options)?;
+        Ok(res)
+    }
+    fn get_id(&self) -> u32;
+    fn get_message_type(&self) -> u8;
+}
+
+/// Check that `response` is the reply to `request`: the message types have to match and, unless
+/// the request was addressed to all devices (device ID 0), so do the device IDs.
+pub fn check_reply<T: Request, S: Response>(request: &T, response: &S) -> Result<()> {
+    if response.get_message_type() != request.get_message_type() {
+        return Err(Error::UnexpectedMessageType(response.get_message_type()));
+    }
+    if request.get_id() != 0 && response.get_id() != request.get_id() {
+        return Err(Error::DeviceIdMismatch {
+            expected: request.get_id(),
+            actual: response.get_id(),
+        });
+    }
+    Ok(())
+}
+
+/// Decode a received datagram as the reply to `request`. Datagrams that are not a reply to
+/// `request`, such as stale replies or replies from other devices, yield `None`. Datagrams that
+/// can't be decoded at all yield an [`Error`].
+pub fn decode_reply<T: Request, S: Response + Debug>(
+    request: &T,
+    datagram: &[u8],
+) -> Result<Option<S>> {
+    let bytes: &[u8; 64] = datagram
+        .try_into()
+        .map_err(|_| Error::Decode(format!("expected 64 bytes, got {}", datagram.len())))?;
+    let response = S::from_bytes(bytes)?;
+    Ok(check_reply(request, &response).ok().map(|_| response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{GetTimeRequest, GetTimeResponse, OpenDoorRequest};
+
+    const GET_TIME_RESPONSE: [u8; 64] = [
+        0x17, 0x32, 0x00, 0x00, 0x2d, 0x55, 0x39, 0x19, 0x20, 0x19, 0x04, 0x19, 0x17, 0x00, 0x09,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn response_with_invalid_header_is_rejected() {
+        let mut bytes = GET_TIME_RESPONSE;
+        bytes[0] = 0x19;
+        assert!(matches!(
+            GetTimeResponse::from_bytes(&bytes),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn decode_reply_discards_short_datagrams() {
+        let request = GetTimeRequest::new(423187757);
+        let response: Result<Option<GetTimeResponse>> =
+            decode_reply(&request, &GET_TIME_RESPONSE[..32]);
+        assert!(matches!(response, Err(Error::Decode(_))));
+        let response: Result<Option<GetTimeResponse>> = decode_reply(&request, &GET_TIME_RESPONSE);
+        assert!(response.unwrap().is_some());
+    }
+
+    #[test]
+    fn decode_reply_ignores_replies_to_other_requests() {
+        let request = GetTimeRequest::new(405419896);
+        let response: Result<Option<GetTimeResponse>> = decode_reply(&request, &GET_TIME_RESPONSE);
+        assert!(response.unwrap().is_none());
+    }
+
+    #[test]
+    fn check_reply_accepts_matching_response() {
+        let response = GetTimeResponse::from_bytes(&GET_TIME_RESPONSE).unwrap();
+        assert!(check_reply(&GetTimeRequest::new(423187757), &response).is_ok());
+        assert!(check_reply(&GetTimeRequest::new(0), &response).is_ok());
+    }
+
+    #[test]
+    fn check_reply_rejects_other_message_type() {
+        let response = GetTimeResponse::from_bytes(&GET_TIME_RESPONSE).unwrap();
+        assert!(matches!(
+            check_reply(&OpenDoorRequest::new(423187757, 1), &response),
+            Err(Error::UnexpectedMessageType(0x32))
+        ));
+    }
+
+    #[test]
+    fn check_reply_rejects_other_device() {
+        let response = GetTimeResponse::from_bytes(&GET_TIME_RESPONSE).unwrap();
+        assert!(matches!(
+            check_reply(&GetTimeRequest::new(405419896), &response),
+            Err(Error::DeviceIdMismatch {
+                expected: 405419896,
+                actual: 423187757
+            })
+        ));
     }
 }
@@ -1,9 +1,9 @@
 use super::bcd;
-use anyhow::Result;
+use crate::{Error, Result};
 use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use std::fmt::Display;
 
-#[derive(bincode::Decode, PartialEq, Eq, Debug)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug)]
 pub struct MacAddress {
     pub addr: (u8, u8, u8, u8, u8, u8),
 }
@@ -24,7 +24,7 @@ impl Display for MacAddress {
     }
 }
 
-#[derive(bincode::Decode, PartialEq, Eq, Debug)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug)]
 pub struct Version {
     pub major: u8,
     pub minor: u8,
@@ -36,7 +36,7 @@ impl Display for Version {
     }
 }
 
-#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default, Clone, Copy)]
 pub struct DateBCD {
     date: (u8, u8, u8, u8), // Y, Y, M, D
 }
@@ -51,9 +51,10 @@ impl DateBCD {
 }
 
 impl TryInto<NaiveDate> for DateBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_into(self) -> Result<NaiveDate> {
-        Ok(NaiveDate::parse_from_str(&self.to_string(), "%Y-%m-%d")?)
+        NaiveDate::parse_from_str(&self.to_string(), "%Y-%m-%d")
+            .map_err(|_| Error::InvalidBcd(self.to_string()))
     }
 }
 
@@ -68,7 +69,7 @@ impl Display for DateBCD {
 }
 
 impl TryFrom<NaiveDate> for DateBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(date: NaiveDate) -> Result<Self> {
         Ok(DateBCD::new(
             date.year() as u16,
@@ -80,7 +81,7 @@ impl TryFrom<NaiveDate> for DateBCD {
 
 // TimeWithoutSecondsBCD
 
-#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default, Clone, Copy)]
 pub struct TimeWithoutSecondsBCD {
     pub hour: u8,
     pub minute: u8,
@@ -96,7 +97,7 @@ impl TimeWithoutSecondsBCD {
 }
 
 impl TryFrom<NaiveTime> for TimeWithoutSecondsBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(time: NaiveTime) -> Result<Self> {
         Ok(TimeWithoutSecondsBCD::new(
             time.hour() as u8,
@@ -106,9 +107,10 @@ impl TryFrom<NaiveTime> for TimeWithoutSecondsBCD {
 }
 
 impl TryInto<NaiveTime> for TimeWithoutSecondsBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_into(self) -> Result<NaiveTime> {
-        Ok(NaiveTime::parse_from_str(&self.to_string(), "%H:%M")?)
+        NaiveTime::parse_from_str(&self.to_string(), "%H:%M")
+            .map_err(|_| Error::InvalidBcd(self.to_string()))
     }
 }
 
@@ -120,7 +122,7 @@ impl Display for TimeWithoutSecondsBCD {
 
 // TimeWithSecondsBCD
 
-#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default, Clone, Copy)]
 pub struct TimeWithSecondsBCD {
     pub hour: u8,
     pub minute: u8,
@@ -138,7 +140,7 @@ impl TimeWithSecondsBCD {
 }
 
 impl TryFrom<NaiveTime> for TimeWithSecondsBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(time: NaiveTime) -> Result<Self> {
         Ok(TimeWithSecondsBCD::new(
             time.hour() as u8,
@@ -149,9 +151,10 @@ impl TryFrom<NaiveTime> for TimeWithSecondsBCD {
 }
 
 impl TryInto<NaiveTime> for TimeWithSecondsBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_into(self) -> Result<NaiveTime> {
-        Ok(NaiveTime::parse_from_str(&self.to_string(), "%H:%M:%S")?)
+        NaiveTime::parse_from_str(&self.to_string(), "%H:%M:%S")
+            .map_err(|_| Error::InvalidBcd(self.to_string()))
     }
 }
 
@@ -165,7 +168,7 @@ impl Display for TimeWithSecondsBCD {
     }
 }
 
-#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default, Clone, Copy)]
 pub struct DateTime {
     pub date: DateBCD,
     pub time: TimeWithSecondsBCD,
@@ -181,7 +184,7 @@ impl DateTime {
 }
 
 impl TryFrom<NaiveDateTime> for DateTime {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(datetime: NaiveDateTime) -> Result<Self> {
         Ok(DateTime::new(
             datetime.year() as u16,
@@ -195,12 +198,10 @@ impl TryFrom<NaiveDateTime> for DateTime {
 }
 
 impl TryInto<NaiveDateTime> for DateTime {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_into(self) -> Result<NaiveDateTime> {
-        Ok(NaiveDateTime::parse_from_str(
-            &self.to_string(),
-            "%Y-%m-%d %H:%M:%S",
-        )?)
+        NaiveDateTime::parse_from_str(&self.to_string(), "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| Error::InvalidBcd(self.to_string()))
     }
 }
 
@@ -210,7 +211,7 @@ impl Display for DateTime {
     }
 }
 
-#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default)]
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default, Clone, Copy)]
 pub struct DateShortBCD {
     bcd: (u8, u8, u8),
 }
@@ -225,9 +226,10 @@ impl DateShortBCD {
 }
 
 impl TryInto<NaiveDate> for DateShortBCD {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_into(self) -> Result<NaiveDate> {
-        Ok(NaiveDate::parse_from_str(&self.to_string(), "%y%m%d")?)
+        NaiveDate::parse_from_str(&self.to_string(), "%y%m%d")
+            .map_err(|_| Error::InvalidBcd(self.to_string()))
     }
 }
 
@@ -237,13 +239,42 @@ impl Display for DateShortBCD {
     }
 }
 
+/// A card PIN, encoded as a 24 bit little endian integer. A PIN of 0 means the card has no PIN.
+#[derive(bincode::Decode, bincode::Encode, PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub struct Pin {
+    pin: (u8, u8, u8),
+}
+
+impl Pin {
+    pub fn new(pin: u32) -> Self {
+        Pin {
+            pin: (pin as u8, (pin >> 8) as u8, (pin >> 16) as u8),
+        }
+    }
+}
+
+impl From<Option<u32>> for Pin {
+    fn from(pin: Option<u32>) -> Self {
+        Pin::new(pin.unwrap_or(0))
+    }
+}
+
+impl From<Pin> for Option<u32> {
+    fn from(pin: Pin) -> Self {
+        match pin.pin.0 as u32 | (pin.pin.1 as u32) << 8 | (pin.pin.2 as u32) << 16 {
+            0 => None,
+            p => Some(p),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_date_bcd_into_naive_date() {
-        let date = NaiveDate::from_ymd(2019, 1, 1);
+        let date = NaiveDate::from_ymd_opt(2019, 1, 1).unwrap();
         let bcd = DateBCD::new(2019, 1, 1);
         let converted: NaiveDate = bcd.try_into().unwrap();
         assert_eq!(converted, date);
@@ -251,7 +282,7 @@ mod tests {
 
     #[test]
     fn test_date_bcd_from_naive_date() {
-        let date = NaiveDate::from_ymd(2019, 1, 1);
+        let date = NaiveDate::from_ymd_opt(2019, 1, 1).unwrap();
         let bcd = DateBCD::new(2019, 1, 1);
         let converted = DateBCD::try_from(date).unwrap();
         assert_eq!(converted, bcd);
@@ -259,7 +290,7 @@ mod tests {
 
     #[test]
     fn test_time_without_seconds_bcd_into_naive_time() {
-        let time = NaiveTime::from_hms(8, 12, 0);
+        let time = NaiveTime::from_hms_opt(8, 12, 0).unwrap();
         let bcd = TimeWithoutSecondsBCD::new(8, 12);
         let converted: NaiveTime = bcd.try_into().unwrap();
         assert_eq!(converted, time);
@@ -273,7 +304,7 @@ mod tests {
 
     #[test]
     fn test_time_without_seconds_bcd_from_naive_time() {
-        let time = NaiveTime::from_hms(8, 12, 0);
+        let time = NaiveTime::from_hms_opt(8, 12, 0).unwrap();
         let bcd = TimeWithoutSecondsBCD::new(8, 12);
         let converted = TimeWithoutSecondsBCD::try_from(time).unwrap();
         assert_eq!(converted, bcd);
@@ -287,7 +318,7 @@ mod tests {
 
     #[test]
     fn test_time_with_seconds_bcd_into_naive_time() {
-        let time = NaiveTime::from_hms(8, 12, 13);
+        let time = NaiveTime::from_hms_opt(8, 12, 13).unwrap();
         let bcd = TimeWithSecondsBCD::new(8, 12, 13);
         let converted: NaiveTime = bcd.try_into().unwrap();
         assert_eq!(converted, time);
@@ -295,7 +326,7 @@ mod tests {
 
     #[test]
     fn test_time_with_seconds_bcd_from_naive_time() {
-        let time = NaiveTime::from_hms(8, 12, 13);
+        let time = NaiveTime::from_hms_opt(8, 12, 13).unwrap();
         let bcd = TimeWithSecondsBCD::new(8, 12, 13);
         let converted = TimeWithSecondsBCD::try_from(time).unwrap();
         assert_eq!(converted, bcd);
@@ -333,7 +364,7 @@ mod tests {
 
     #[test]
     fn test_date_short_bcd_into_naive_date() {
-        let date = NaiveDate::from_ymd(2019, 1, 1);
+        let date = NaiveDate::from_ymd_opt(2019, 1, 1).unwrap();
         let bcd = DateShortBCD::new(2019, 1, 1);
         let converted: NaiveDate = bcd.try_into().unwrap();
         assert_eq!(converted, date);
@@ -344,4 +375,12 @@ mod tests {
         let date_time = DateShortBCD::new(2019, 8, 1);
         assert_eq!(date_time.to_string(), "190801");
     }
+
+    #[test]
+    fn test_pin_round_trip() {
+        let pin: Option<u32> = Pin::new(999999).into();
+        assert_eq!(pin, Some(999999));
+        let pin: Option<u32> = Pin::from(None).into();
+        assert_eq!(pin, None);
+    }
 }
@@ -0,0 +1,78 @@
+use crate::messages::RequestResponseType;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Counters of the requests of a single message type sent by an [`Uhppoted`](crate::Uhppoted),
+/// returned by [`Uhppoted::request_metrics`](crate::Uhppoted::request_metrics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestMetrics {
+    /// Requests sent.
+    pub requests: u64,
+    /// Replies received.
+    pub responses: u64,
+    /// Requests that didn't get a reply in time.
+    pub timeouts: u64,
+    /// Received datagrams that couldn't be decoded.
+    pub decode_errors: u64,
+    /// Total time between sending a request and receiving its reply, over all `responses`.
+    pub latency: Duration,
+}
+
+/// [`RequestMetrics`] per message type.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    requests: Mutex<BTreeMap<u8, RequestMetrics>>,
+}
+
+impl Metrics {
+    /// Update the [`RequestMetrics`] of `message_type` with `f`.
+    pub(crate) fn record(&self, message_type: u8, f: impl FnOnce(&mut RequestMetrics)) {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        f(requests.entry(message_type).or_default());
+    }
+
+    /// The [`RequestMetrics`] per message type, keyed by name (e.g. `GetStatus`).
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, RequestMetrics> {
+        let requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        requests
+            .iter()
+            .map(|(message_type, metrics)| (name(*message_type), *metrics))
+            .collect()
+    }
+}
+
+fn name(message_type: u8) -> String {
+    match RequestResponseType::try_from(message_type) {
+        // The firmware calls it status, but it's what `Device::get_status` sends.
+        Ok(RequestResponseType::Status) => "GetStatus".to_string(),
+        Ok(t) => format!("{:?}", t),
+        Err(_) => format!("{:#04x}", message_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_per_message_type() {
+        let metrics = Metrics::default();
+        metrics.record(RequestResponseType::GetTime.into(), |m| m.requests += 1);
+        metrics.record(RequestResponseType::GetTime.into(), |m| m.timeouts += 1);
+        metrics.record(RequestResponseType::Status.into(), |m| m.requests += 1);
+        metrics.record(0x01, |m| m.decode_errors += 1);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot["GetTime"],
+            RequestMetrics {
+                requests: 1,
+                timeouts: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(snapshot["GetStatus"].requests, 1);
+        assert_eq!(snapshot["0x01"].decode_errors, 1);
+    }
+}
@@ -0,0 +1,440 @@
+//! A bridge that publishes events and status of devices to an MQTT broker and executes commands
+//! received from it.
+use crate::{Card, Device, DoorControl, Error, Result, Status, Uhppoted};
+use rumqttc::{Client, ClientError, Event, MqttOptions, Packet, Publish, QoS};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often [`MqttBridge::run`] checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait before reconnecting after the connection to the broker failed.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Configuration of an [`MqttBridge`].
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    /// Host name or IP address of the MQTT broker.
+    pub broker: String,
+    /// Port of the MQTT broker, usually 1883.
+    pub port: u16,
+    pub client_id: String,
+    /// Prefix of all topics, for instance `uhppote`.
+    pub prefix: String,
+    /// Local address to receive [`Status`] messages on.
+    pub bind: SocketAddr,
+    /// Address the devices should send [`Status`] messages to. This is the address of this host
+    /// as seen by the devices, which differs from `bind` when binding to `0.0.0.0`.
+    pub listener: SocketAddr,
+    /// IDs and (optional) IP addresses of the devices to bridge.
+    /// A [`Controller`](crate::Controller) registered with [`Uhppoted::controllers`] for one of
+    /// these IDs takes precedence over the address given here.
+    pub devices: Vec<(u32, Option<Ipv4Addr>)>,
+    /// How often to publish the [`Status`] of every device. `None` only publishes the
+    /// [`Status`] messages sent by the devices.
+    pub status_interval: Option<Duration>,
+}
+
+/// A command received on a command topic.
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    OpenDoor { door: u8 },
+    SetDoorControl { door: u8, control: DoorControl },
+    AddCard(Card),
+    DeleteCard { number: u32 },
+}
+
+/// Function the [`MqttBridge`] reports errors of a device to.
+type ErrorHandler = Box<dyn FnMut(u32, &Error) + Send>;
+
+/// What the connection thread and the [`Listener`](crate::Listener) report to
+/// [`MqttBridge::run`].
+enum Message {
+    Connected,
+    Publish(Publish),
+    Status(Status),
+}
+
+/// Bridges devices to an MQTT broker.
+///
+/// On start, the bridge registers itself as the listener of every configured device with
+/// [`Device::set_listener`]. It publishes to these topics, with `{prefix}` and `{id}` the topic
+/// prefix and the device ID:
+///
+/// | Topic | Payload |
+/// |-------|---------|
+/// | `{prefix}/{id}/status` | every [`Status`] received from the device, and periodic [`Device::get_status`] snapshots (retained) |
+/// | `{prefix}/{id}/events` | every new [`Event`](crate::Event), with the `device_id` added |
+/// | `{prefix}/{id}/replies/{command}` | `{"ok": true}` or `{"error": "..."}` for every command |
+///
+/// Commands are received on `{prefix}/{id}/commands/{command}`:
+///
+/// | Command | Payload | Device operation |
+/// |---------|---------|------------------|
+/// | `open-door` | `{"door": 1}` | [`Device::open_door`] |
+/// | `set-door-control` | `{"door": 1, "mode": "normally_open", "delay": 5}` | [`Device::set_door_control_state`] |
+/// | `add-card` | [`Card`] | [`Device::add_card`] |
+/// | `delete-card` | `{"number": 12345}` | [`Device::delete_card`] |
+///
+/// A `request_id` in the payload of a command is copied to its reply. Commands for devices that
+/// are not in [`MqttConfig::devices`] are answered with an error.
+///
+/// A device that can't be registered as the listener on start is reported to the handler set
+/// with [`MqttBridge::on_error`], by default printed to stderr, and bridged all the same.
+///
+/// Example:
+/// ```no_run
+/// use std::sync::atomic::AtomicBool;
+/// use std::time::Duration;
+/// use uhppote_rs::{MqttBridge, MqttConfig, Uhppoted};
+/// let config = MqttConfig {
+///     broker: "localhost".to_string(),
+///     port: 1883,
+///     client_id: "uhppote".to_string(),
+///     prefix: "uhppote".to_string(),
+///     bind: "0.0.0.0:60001".parse().unwrap(),
+///     listener: "192.168.1.10:60001".parse().unwrap(),
+///     devices: vec![(423196779, None)],
+///     status_interval: Some(Duration::from_secs(60)),
+/// };
+/// let mut bridge = MqttBridge::new(Uhppoted::default(), config);
+/// bridge.run(&AtomicBool::new(false)).unwrap();
+/// ```
+pub struct MqttBridge {
+    uhppoted: Uhppoted,
+    config: MqttConfig,
+    last_index: HashMap<u32, u32>,
+    on_error: ErrorHandler,
+}
+
+impl MqttBridge {
+    pub fn new(uhppoted: Uhppoted, config: MqttConfig) -> MqttBridge {
+        MqttBridge {
+            uhppoted,
+            config,
+            last_index: HashMap::new(),
+            on_error: Box::new(|id, e| eprintln!("device {}: {}", id, e.report())),
+        }
+    }
+
+    /// Report errors of devices to `handler`, with the ID of the device, instead of printing
+    /// them to stderr.
+    pub fn on_error<F: FnMut(u32, &Error) + Send + 'static>(mut self, handler: F) -> Self {
+        self.on_error = Box::new(handler);
+        self
+    }
+
+    /// Register as the listener on all devices and bridge them to the broker until `stop` is
+    /// set.
+    pub fn run(&mut self, stop: &AtomicBool) -> Result<()> {
+        let (ip, port) = match self.config.listener {
+            SocketAddr::V4(a) => (*a.ip(), a.port()),
+            SocketAddr::V6(a) => {
+                return Err(Error::InvalidArgument(format!(
+                    "listener must be an IPv4 address, got {}",
+                    a
+                )))
+            }
+        };
+
+        let (tx, messages) = mpsc::channel();
+        let mut listener = self.uhppoted.listener(self.config.bind)?;
+        for (id, address) in &self.config.devices {
+            listener = listener.device(*id);
+            // One unreachable device is no reason not to bridge the others.
+            if let Err(e) = self
+                .uhppoted
+                .listed_device(*id, *address)
+                .set_listener(ip, port)
+            {
+                (self.on_error)(*id, &e);
+            }
+        }
+        let handle = {
+            let tx = tx.clone();
+            listener.spawn(move |status| {
+                // Undecodable packets are not worth stopping for.
+                if let Ok(status) = status {
+                    let _ = tx.send(Message::Status(status));
+                }
+            })?
+        };
+
+        let mut options = MqttOptions::new(
+            self.config.client_id.as_str(),
+            self.config.broker.as_str(),
+            self.config.port,
+        );
+        options.set_keep_alive(Duration::from_secs(30));
+        let (client, connection) = Client::new(options, 64);
+        let connection = Connection::spawn(connection, tx);
+
+        let mut next_poll = Instant::now();
+        while !stop.load(Ordering::Relaxed) {
+            if let Some(interval) = self.config.status_interval {
+                if Instant::now() >= next_poll {
+                    next_poll += interval;
+                    self.publish_statuses(&client)?;
+                }
+            }
+            match messages.recv_timeout(POLL_INTERVAL) {
+                Ok(Message::Connected) => {
+                    // Subscriptions don't survive a reconnect, so renew them on every connect.
+                    let topic = format!("{}/+/commands/+", self.config.prefix);
+                    client
+                        .subscribe(topic, QoS::AtLeastOnce)
+                        .map_err(mqtt_error)?;
+                }
+                Ok(Message::Publish(publish)) => self.handle_command(&client, &publish)?,
+                Ok(Message::Status(status)) => self.handle_status(&client, status)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        handle.stop();
+        let _ = client.disconnect();
+        connection.stop();
+        Ok(())
+    }
+
+    fn device(&self, id: u32) -> Device<'_> {
+        let address = self
+            .config
+            .devices
+            .iter()
+            .find(|(d, _)| *d == id)
+            .and_then(|(_, address)| *address);
+        self.uhppoted.listed_device(id, address)
+    }
+
+    fn publish(&self, client: &Client, topic: String, payload: &Value, retain: bool) -> Result<()> {
+        client
+            .publish(topic, QoS::AtLeastOnce, retain, payload.to_string())
+            .map_err(mqtt_error)
+    }
+
+    fn publish_statuses(&self, client: &Client) -> Result<()> {
+        for (id, _) in &self.config.devices {
+            // A device that doesn't respond is reported again on the next poll.
+            if let Ok(status) = self.device(*id).get_status() {
+                let topic = format!("{}/{}/status", self.config.prefix, id);
+                self.publish(client, topic, &to_value(&status)?, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_status(&mut self, client: &Client, status: Status) -> Result<()> {
+        let id = status.device_id;
+        let topic = format!("{}/{}/status", self.config.prefix, id);
+        self.publish(client, topic, &to_value(&status)?, false)?;
+
+        let event = match status.last_event {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        if self
+            .last_index
+            .get(&id)
+            .is_some_and(|last| event.index <= *last)
+        {
+            return Ok(());
+        }
+        self.last_index.insert(id, event.index);
+
+        let mut payload = to_value(&event)?;
+        if let Value::Object(fields) = &mut payload {
+            fields.insert("device_id".to_string(), id.into());
+        }
+        let topic = format!("{}/{}/events", self.config.prefix, id);
+        self.publish(client, topic, &payload, false)
+    }
+
+    fn handle_command(&self, client: &Client, publish: &Publish) -> Result<()> {
+        let (id, name) = match parse_topic(&self.config.prefix, &publish.topic) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        let payload: Value = serde_json::from_slice(&publish.payload).unwrap_or(Value::Null);
+
+        let result = if self.config.devices.iter().any(|(d, _)| *d == id) {
+            parse_command(name, &payload)
+        } else {
+            Err(Error::InvalidArgument(format!("unknown device: {}", id)))
+        };
+        let result = result.and_then(|command| {
+            let device = self.device(id);
+            match command {
+                Command::OpenDoor { door } => device.open_door(door),
+                Command::SetDoorControl { door, control } => {
+                    device.set_door_control_state(door, control).map(|_| ())
+                }
+                Command::AddCard(card) => device.add_card(card),
+                Command::DeleteCard { number } => device.delete_card(number),
+            }
+        });
+
+        let mut reply = match result {
+            Ok(()) => json!({ "ok": true }),
+            Err(e) => json!({ "error": e.report() }),
+        };
+        if let Some(request_id) = payload.get("request_id") {
+            reply["request_id"] = request_id.clone();
+        }
+        let topic = format!("{}/{}/replies/{}", self.config.prefix, id, name);
+        self.publish(client, topic, &reply, false)
+    }
+}
+
+/// Drives the MQTT connection on a background thread.
+struct Connection {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Connection {
+    fn spawn(mut connection: rumqttc::Connection, tx: Sender<Message>) -> Connection {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let message = match connection.recv_timeout(POLL_INTERVAL) {
+                        Ok(Ok(Event::Incoming(Packet::ConnAck(_)))) => Message::Connected,
+                        Ok(Ok(Event::Incoming(Packet::Publish(publish)))) => {
+                            Message::Publish(publish)
+                        }
+                        Ok(Ok(_)) | Err(rumqttc::RecvTimeoutError::Timeout) => continue,
+                        // The next poll reconnects.
+                        Ok(Err(_)) => {
+                            std::thread::sleep(RECONNECT_DELAY);
+                            continue;
+                        }
+                        Err(rumqttc::RecvTimeoutError::Disconnected) => break,
+                    };
+                    if tx.send(message).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+        Connection { stop, thread }
+    }
+
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.thread.join();
+    }
+}
+
+/// Split a command topic `{prefix}/{id}/commands/{command}` into the device ID and command.
+fn parse_topic<'a>(prefix: &str, topic: &'a str) -> Option<(u32, &'a str)> {
+    let rest = topic.strip_prefix(prefix)?.strip_prefix('/')?;
+    match rest.split('/').collect::<Vec<_>>().as_slice() {
+        [id, "commands", command] => Some((id.parse().ok()?, command)),
+        _ => None,
+    }
+}
+
+fn parse_command(name: &str, payload: &Value) -> Result<Command> {
+    let command = match name {
+        "open-door" => Command::OpenDoor {
+            door: field(payload, "door")?,
+        },
+        "set-door-control" => Command::SetDoorControl {
+            door: field(payload, "door")?,
+            control: from_value(payload)?,
+        },
+        "add-card" => Command::AddCard(from_value(payload)?),
+        "delete-card" => Command::DeleteCard {
+            number: field(payload, "number")?,
+        },
+        _ => return Err(Error::InvalidArgument(format!("unknown command: {}", name))),
+    };
+    Ok(command)
+}
+
+fn field<T: serde::de::DeserializeOwned>(payload: &Value, name: &str) -> Result<T> {
+    match payload.get(name) {
+        Some(value) => from_value(value),
+        None => Err(Error::InvalidArgument(format!("missing field: {}", name))),
+    }
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(value: &Value) -> Result<T> {
+    T::deserialize(value).map_err(|e| Error::InvalidArgument(e.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| Error::Decode(e.to_string()))
+}
+
+fn mqtt_error(e: ClientError) -> Error {
+    Error::Io(io::Error::other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DoorControlMode;
+
+    #[test]
+    fn topics() {
+        assert_eq!(
+            parse_topic("uhppote", "uhppote/423196779/commands/open-door"),
+            Some((423196779, "open-door"))
+        );
+        assert_eq!(
+            parse_topic("site/a", "site/a/423196779/commands/add-card"),
+            Some((423196779, "add-card"))
+        );
+        assert_eq!(parse_topic("uhppote", "uhppote/423196779/events"), None);
+        assert_eq!(
+            parse_topic("uhppote", "uhppote/abc/commands/open-door"),
+            None
+        );
+        assert_eq!(parse_topic("uhppote", "other/1/commands/open-door"), None);
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            parse_command("open-door", &json!({ "door": 2, "request_id": "x" })).unwrap(),
+            Command::OpenDoor { door: 2 }
+        );
+        assert_eq!(
+            parse_command(
+                "set-door-control",
+                &json!({ "door": 1, "mode": "normally_open", "delay": 5 })
+            )
+            .unwrap(),
+            Command::SetDoorControl {
+                door: 1,
+                control: DoorControl {
+                    mode: DoorControlMode::NormallyOpen,
+                    delay: Duration::from_secs(5),
+                }
+            }
+        );
+        assert_eq!(
+            parse_command("delete-card", &json!({ "number": 12345 })).unwrap(),
+            Command::DeleteCard { number: 12345 }
+        );
+        assert!(matches!(
+            parse_command("open-door", &json!({})),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            parse_command("reboot", &json!({})),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
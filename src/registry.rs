@@ -0,0 +1,194 @@
+use crate::{TcpTransport, Transport, UHPPOTE_PORT};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How requests reach a [`Controller`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Protocol {
+    /// UDP datagrams, as supported by all controllers.
+    #[default]
+    Udp,
+    /// A TCP connection, see [`TcpTransport`].
+    Tcp,
+}
+
+/// How to reach a single controller, registered with
+/// [`Uhppoted::controllers`](crate::Uhppoted::controllers). [`Uhppoted::get_device`](crate::Uhppoted::get_device)
+/// uses it for [`Device`](crate::Device)s with the same `id`.
+///
+/// With the `config` feature enabled, controllers can be loaded from a TOML file with
+/// [`Controller::load`]:
+/// ```toml
+/// [[controller]]
+/// id = 423196779
+/// address = "192.168.1.100"
+///
+/// [[controller]]
+/// id = 405419896
+/// address = "10.0.0.2"
+/// port = 60005
+/// protocol = "tcp"
+/// timeout = 10.0
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Controller {
+    /// The device ID of the controller.
+    pub id: u32,
+    /// The IP address requests are sent to. For a controller behind NAT, the address of the
+    /// router forwarding to it.
+    pub address: Ipv4Addr,
+    /// The port requests are sent to. Controllers listen on 60000.
+    #[cfg_attr(feature = "serde", serde(default = "default_port"))]
+    pub port: u16,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub protocol: Protocol,
+    /// How long to wait for a reply, instead of the timeout of [`Uhppoted`](crate::Uhppoted).
+    /// Stored in configuration files as seconds.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            with = "crate::seconds::option",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub timeout: Option<Duration>,
+}
+
+impl Controller {
+    /// A [`Controller`] at port 60000 of `address`, reached over UDP with the timeout of
+    /// [`Uhppoted`](crate::Uhppoted).
+    pub fn new(id: u32, address: Ipv4Addr) -> Controller {
+        Controller {
+            id,
+            address,
+            port: UHPPOTE_PORT,
+            protocol: Protocol::Udp,
+            timeout: None,
+        }
+    }
+
+    /// The address and port requests are sent to.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.address.into(), self.port)
+    }
+
+    /// Read the `[[controller]]` tables of the TOML file at `path`.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use uhppote_rs::{Controller, Uhppoted};
+    /// let uhppoted = Uhppoted::default().controllers(Controller::load("controllers.toml").unwrap());
+    /// let device = uhppoted.get_device(423196779, None);
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> crate::Result<Vec<Controller>> {
+        #[derive(serde::Deserialize)]
+        struct File {
+            #[serde(default)]
+            controller: Vec<Controller>,
+        }
+
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let file: File = toml::from_str(&contents).map_err(|e| {
+            crate::Error::InvalidArgument(format!("invalid {}: {}", path.display(), e))
+        })?;
+        Ok(file.controller)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_port() -> u16 {
+    UHPPOTE_PORT
+}
+
+/// The registered [`Controller`]s of an [`Uhppoted`](crate::Uhppoted). Controllers reached over
+/// TCP share a single [`TcpTransport`], so all their [`Device`](crate::Device)s use the same
+/// connection.
+#[derive(Debug, Default)]
+pub(crate) struct Registry {
+    controllers: HashMap<u32, Registered>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Registered {
+    pub(crate) controller: Controller,
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+}
+
+impl Registry {
+    /// Register `controller`, replacing an earlier registration with the same ID.
+    pub(crate) fn insert(&mut self, controller: Controller) {
+        let transport: Option<Arc<dyn Transport>> = match controller.protocol {
+            Protocol::Udp => None,
+            Protocol::Tcp => Some(Arc::new(TcpTransport::new(controller.socket_addr()))),
+        };
+        self.controllers.insert(
+            controller.id,
+            Registered {
+                controller,
+                transport,
+            },
+        );
+    }
+
+    pub(crate) fn get(&self, id: u32) -> Option<&Registered> {
+        self.controllers.get(&id)
+    }
+}
+
+#[cfg(all(test, feature = "config"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load() {
+        let dir = std::env::temp_dir().join(format!("uhppote-registry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("controllers.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[controller]]
+            id = 423196779
+            address = "192.168.1.100"
+
+            [[controller]]
+            id = 405419896
+            address = "10.0.0.2"
+            port = 60005
+            protocol = "tcp"
+            timeout = 2.5
+            "#,
+        )
+        .unwrap();
+
+        let controllers = Controller::load(&path).unwrap();
+        assert_eq!(
+            controllers,
+            vec![
+                Controller::new(423196779, Ipv4Addr::new(192, 168, 1, 100)),
+                Controller {
+                    id: 405419896,
+                    address: Ipv4Addr::new(10, 0, 0, 2),
+                    port: 60005,
+                    protocol: Protocol::Tcp,
+                    timeout: Some(Duration::from_millis(2500)),
+                },
+            ]
+        );
+
+        std::fs::write(&path, "[[controller]]\nid = 1\n").unwrap();
+        assert!(matches!(
+            Controller::load(&path),
+            Err(crate::Error::InvalidArgument(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+use crate::messages::RequestResponseType;
+use crate::Error;
+use std::time::Duration;
+
+/// When and how often [`Uhppoted`](crate::Uhppoted) retries a request that got no reply, set
+/// with [`Uhppoted::retry_policy`](crate::Uhppoted::retry_policy).
+///
+/// Requests that only read from a device (`get_status`, `get_card_by_id`, `get_event`,
+/// `get_time_profile` and so on) are retried up to `attempts` times. Requests that change a device
+/// are only retried when `retry_writes` is set, since a write whose reply was lost may have been
+/// applied already. Opening a door twice or adding a task twice is not harmless.
+///
+/// Before the n<sup>th</sup> retry, [`Uhppoted`](crate::Uhppoted) waits `backoff * 2^(n-1)`, at
+/// most `max_backoff`, varied randomly by up to `jitter` (a fraction) to keep many clients from
+/// retrying in lockstep. When all attempts fail, the error is returned as [`Error::Retried`].
+///
+/// Example:
+/// ```no_run
+/// use std::time::Duration;
+/// use uhppote_rs::{RetryPolicy, Uhppoted};
+/// let uhppoted = Uhppoted::default().retry_policy(RetryPolicy {
+///     attempts: 3,
+///     attempt_timeout: Some(Duration::from_millis(500)),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts per request, including the first. 1 disables retries.
+    pub attempts: u32,
+    /// How long to wait for a reply to each attempt. `None` uses the timeout of
+    /// [`Uhppoted`](crate::Uhppoted). The timeout of a registered [`Controller`](crate::Controller)
+    /// takes precedence.
+    pub attempt_timeout: Option<Duration>,
+    /// Wait before the first retry.
+    pub backoff: Duration,
+    /// Longest wait between retries.
+    pub max_backoff: Duration,
+    /// Fraction (0-1) by which the wait is varied randomly.
+    pub jitter: f64,
+    /// Also retry requests that change the device.
+    pub retry_writes: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Up to 3 attempts for requests that only read from a device, a single attempt for writes.
+    /// Retries back off from 100ms up to 2s with 20% jitter.
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 3,
+            attempt_timeout: None,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            jitter: 0.2,
+            retry_writes: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The number of attempts for a request of `message_type`.
+    pub(crate) fn attempts(&self, message_type: u8) -> u32 {
+        let read = RequestResponseType::try_from(message_type).is_ok_and(|t| t.is_read());
+        if read || self.retry_writes {
+            self.attempts.max(1)
+        } else {
+            1
+        }
+    }
+
+    /// How long to wait before retry number `retry` (starting at 1).
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self.backoff.saturating_mul(factor).min(self.max_backoff);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 - jitter + 2.0 * jitter * fastrand::f64())
+    }
+}
+
+impl RequestResponseType {
+    /// Whether the request only reads from the device, so sending it again is harmless.
+    pub(crate) fn is_read(&self) -> bool {
+        matches!(
+            self,
+            RequestResponseType::Status
+                | RequestResponseType::GetTime
+                | RequestResponseType::GetCards
+                | RequestResponseType::GetCardByID
+                | RequestResponseType::GetCardByIndex
+                | RequestResponseType::GetDoorControlState
+                | RequestResponseType::GetAntiPassback
+                | RequestResponseType::GetListener
+                | RequestResponseType::GetConfig
+                | RequestResponseType::GetTimeProfile
+                | RequestResponseType::GetEvent
+                | RequestResponseType::GetEventIndex
+        )
+    }
+}
+
+impl Error {
+    /// Whether the error may go away when the request is sent again.
+    pub(crate) fn is_transient(&self) -> bool {
+        matches!(self, Error::Timeout | Error::Io(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_reads_are_retried_by_default() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            ..Default::default()
+        };
+        assert_eq!(policy.attempts(RequestResponseType::GetEvent.into()), 3);
+        assert_eq!(policy.attempts(RequestResponseType::Status.into()), 3);
+        assert_eq!(policy.attempts(RequestResponseType::OpenDoor.into()), 1);
+        assert_eq!(policy.attempts(RequestResponseType::AddTask.into()), 1);
+
+        let policy = RetryPolicy {
+            retry_writes: true,
+            ..policy
+        };
+        assert_eq!(policy.attempts(RequestResponseType::OpenDoor.into()), 3);
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            jitter: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_in_range() {
+        let policy = RetryPolicy {
+            backoff: Duration::from_millis(100),
+            jitter: 0.5,
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let delay = policy.backoff(1);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(150));
+        }
+    }
+}
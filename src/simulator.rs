@@ -0,0 +1,684 @@
+//! An in-process simulator of a UHPPOTE controller, intended for tests.
+//!
+//! The [`Simulator`] binds a UDP socket, decodes every request this crate knows how to send and
+//! replies the way the controller firmware does. All state (cards, events, time profiles, tasks,
+//! door control states, the listener, ...) is kept in memory.
+//!
+//! Example:
+//! ```no_run
+//! use uhppote_rs::simulator::Simulator;
+//! use uhppote_rs::Uhppoted;
+//! let simulator = Simulator::start(423196779, "127.0.0.1:60000".parse().unwrap()).unwrap();
+//! let uhppoted = Uhppoted::default();
+//! let device = uhppoted.get_device(423196779, Some("127.0.0.1".parse().unwrap()));
+//! let status = device.get_status().unwrap();
+//! simulator.stop();
+//! ```
+//!
+//! [`Device`](crate::Device)s send requests to port 60000, unless a
+//! [`Controller`](crate::Controller) with another port is registered for the device ID, so a
+//! simulator can listen on any port:
+//! ```no_run
+//! use uhppote_rs::simulator::Simulator;
+//! use uhppote_rs::{Controller, Uhppoted};
+//! let simulator = Simulator::start(423196779, "127.0.0.1:60005".parse().unwrap()).unwrap();
+//! let uhppoted = Uhppoted::default().controllers([Controller {
+//!     port: 60005,
+//!     ..Controller::new(423196779, "127.0.0.1".parse().unwrap())
+//! }]);
+//! let status = uhppoted.get_device(423196779, None).get_status().unwrap();
+//! ```
+use crate::cards::DELETED_CARD;
+use crate::messages::types::{DateBCD, DateShortBCD, DateTime, MacAddress, Pin, Version};
+use crate::messages::*;
+use crate::types::Event;
+use crate::Result;
+use chrono::{Duration as ChronoDuration, Local, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Magic word the firmware expects for destructive operations.
+const MAGIC_WORD: u32 = 0x55aaaa55;
+
+/// A simulated UHPPOTE controller, answering requests on a local UDP socket until it is stopped
+/// or dropped.
+pub struct Simulator {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Simulator {
+    /// Start a simulated controller with `device_id` that listens on `address`, usually a
+    /// loopback address like `127.0.0.1:60000`. For any port other than 60000, register a
+    /// [`Controller`](crate::Controller) with that port.
+    pub fn start(device_id: u32, address: SocketAddr) -> Result<Simulator> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+        let address = socket.local_addr()?;
+        let state = Arc::new(Mutex::new(State::new(device_id, address)));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || run(socket, state, stop))
+        };
+
+        Ok(Simulator {
+            address,
+            state,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the [`Simulator`] is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Add an [`Event`] to the event log, as if it happened on the controller. The index of
+    /// `event` is ignored; the index assigned by the simulator is returned. When a listener is
+    /// set, a [`Status`](crate::Status) message with the new event is sent to it.
+    pub fn add_event(&self, event: Event) -> Result<u32> {
+        let mut state = self.state.lock().unwrap();
+        let index = state.push_event(EventRecord {
+            type_: event.event_type as u8,
+            granted: event.granted,
+            door: event.door,
+            direction: event.direction as u8,
+            card_number: event.card_number,
+            timestamp: event.timestamp.try_into()?,
+            reason: event.reason as u8,
+        });
+        state.notify_listener()?;
+        Ok(index)
+    }
+
+    /// Mark all events up to and including `index` as overwritten, as happens when the event
+    /// log of a controller wraps around.
+    pub fn overwrite_events(&self, index: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.overwritten = index.min(state.events.len() as u32);
+    }
+
+    /// Garble the timestamp of the event at `index`, so reading it fails to decode.
+    pub fn corrupt_event(&self, index: u32) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(event) = (index as usize)
+            .checked_sub(1)
+            .and_then(|i| state.events.get_mut(i))
+        {
+            event.timestamp = DateTime::default();
+        }
+    }
+
+    /// Handle the next `n` requests without sending a reply, as if the replies were lost on the
+    /// network.
+    pub fn drop_replies(&self, n: u32) {
+        self.state.lock().unwrap().drop_replies = n;
+    }
+
+    /// Stop the [`Simulator`] and wait for it to shut down.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Receive requests and send replies until `stop` is set.
+fn run(socket: UdpSocket, state: Arc<Mutex<State>>, stop: Arc<AtomicBool>) {
+    let mut buf = [0u8; 64];
+    while !stop.load(Ordering::Relaxed) {
+        let (n, from) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        if n != buf.len() {
+            continue;
+        }
+        let mut state = state.lock().unwrap();
+        if let Ok(Some(reply)) = state.handle(&buf) {
+            if state.drop_replies > 0 {
+                state.drop_replies -= 1;
+            } else {
+                let _ = socket.send_to(&reply, from);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct CardRecord {
+    number: u32,
+    from: DateBCD,
+    to: DateBCD,
+    doors: [u8; 4],
+    pin: Pin,
+}
+
+#[derive(Clone, Copy, Default)]
+struct EventRecord {
+    type_: u8,
+    granted: bool,
+    door: u8,
+    direction: u8,
+    card_number: u32,
+    timestamp: DateTime,
+    reason: u8,
+}
+
+struct State {
+    device_id: u32,
+    address: Ipv4Addr,
+    subnet: Ipv4Addr,
+    gateway: Ipv4Addr,
+    clock_offset: ChronoDuration,
+    cards: Vec<Option<CardRecord>>,
+    events: Vec<EventRecord>,
+    overwritten: u32,
+    event_index: u32,
+    record_special_events: bool,
+    time_profiles: BTreeMap<u8, SetTimeProfileRequest>,
+    tasks: Vec<AddTaskRequest>,
+    door_control: [(u8, u8); 4],
+    listener: (Ipv4Addr, u16),
+    antipassback: u8,
+    interlock: u8,
+    keypads: [bool; 4],
+    passcodes: [[u32; 4]; 4],
+    first_cards: BTreeMap<u8, SetFirstCardRequest>,
+    sequence_id: u32,
+    drop_replies: u32,
+}
+
+impl State {
+    fn new(device_id: u32, address: SocketAddr) -> State {
+        let address = match address {
+            SocketAddr::V4(a) => *a.ip(),
+            SocketAddr::V6(_) => Ipv4Addr::LOCALHOST,
+        };
+        State {
+            device_id,
+            address,
+            subnet: Ipv4Addr::new(255, 255, 255, 0),
+            gateway: Ipv4Addr::UNSPECIFIED,
+            clock_offset: ChronoDuration::zero(),
+            cards: Vec::new(),
+            events: Vec::new(),
+            overwritten: 0,
+            event_index: 0,
+            record_special_events: false,
+            time_profiles: BTreeMap::new(),
+            tasks: Vec::new(),
+            door_control: [(3, 5); 4],
+            listener: (Ipv4Addr::UNSPECIFIED, 0),
+            antipassback: 0,
+            interlock: 0,
+            keypads: [false; 4],
+            passcodes: [[0; 4]; 4],
+            first_cards: BTreeMap::new(),
+            sequence_id: 0,
+            drop_replies: 0,
+        }
+    }
+
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local() + self.clock_offset
+    }
+
+    fn push_event(&mut self, event: EventRecord) -> u32 {
+        self.events.push(event);
+        self.events.len() as u32
+    }
+
+    fn card_slot(&self, number: u32) -> Option<usize> {
+        self.cards
+            .iter()
+            .position(|c| matches!(c, Some(c) if c.number == number))
+    }
+
+    /// Build a [`GetStatusResponse`] describing the current state and the last event.
+    fn status(&mut self) -> Result<GetStatusResponse> {
+        let now = self.now();
+        let event = self.events.last().copied().unwrap_or_default();
+        self.sequence_id += 1;
+        Ok(GetStatusResponse::new(
+            self.device_id,
+            self.events.len() as u32,
+            event.type_,
+            event.granted,
+            event.door,
+            event.direction,
+            event.card_number,
+            event.timestamp,
+            event.reason,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            0,
+            now.time().try_into()?,
+            self.sequence_id,
+            0,
+            0,
+            0,
+            DateShortBCD::new(
+                chrono::Datelike::year(&now) as u16,
+                chrono::Datelike::month(&now) as u8,
+                chrono::Datelike::day(&now) as u8,
+            ),
+        ))
+    }
+
+    /// Send a [`GetStatusResponse`] to the listener, if one is set.
+    fn notify_listener(&mut self) -> Result<()> {
+        let (ip, port) = self.listener;
+        if ip.is_unspecified() || port == 0 {
+            return Ok(());
+        }
+        let status = self.status()?;
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.send_to(&status.to_bytes(), SocketAddr::from((ip, port)))?;
+        Ok(())
+    }
+
+    /// Handle a single request, returning the reply to send, if any.
+    fn handle(&mut self, buf: &[u8; 64]) -> Result<Option<[u8; 64]>> {
+        let message_type: RequestResponseType = buf[1].try_into()?;
+        let device_id = u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]);
+        let id = self.device_id;
+
+        // Controllers only answer requests addressed to them; discovery is addressed to all.
+        let discovery = matches!(message_type, RequestResponseType::GetConfig) && device_id == 0;
+        if device_id != id && !discovery {
+            return Ok(None);
+        }
+
+        let reply = match message_type {
+            RequestResponseType::Status => {
+                GetStatusRequest::from_bytes(buf)?;
+                self.status()?.to_bytes()
+            }
+            RequestResponseType::SetTime => {
+                let r = SetTimeRequest::from_bytes(buf)?;
+                let datetime: NaiveDateTime = r.datetime.try_into()?;
+                self.clock_offset = datetime - Local::now().naive_local();
+                SetTimeResponse::new(id, self.now().try_into()?).to_bytes()
+            }
+            RequestResponseType::GetTime => {
+                GetTimeRequest::from_bytes(buf)?;
+                GetTimeResponse::new(id, self.now().try_into()?).to_bytes()
+            }
+            RequestResponseType::OpenDoor => {
+                let r = OpenDoorRequest::from_bytes(buf)?;
+                let valid = (1..=4).contains(&r.door_id);
+                if valid {
+                    let timestamp = self.now().try_into()?;
+                    self.push_event(EventRecord {
+                        type_: 2,
+                        granted: true,
+                        door: r.door_id,
+                        direction: 1,
+                        card_number: 0,
+                        timestamp,
+                        reason: 44,
+                    });
+                    self.notify_listener()?;
+                }
+                OpenDoorResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::PutCard => {
+                let r = PutCardRequest::from_bytes(buf)?;
+                let card = CardRecord {
+                    number: r.card_id,
+                    from: r.from,
+                    to: r.to,
+                    doors: [r.door_1, r.door_2, r.door_3, r.door_4],
+                    pin: r.pin,
+                };
+                match self.card_slot(r.card_id) {
+                    Some(slot) => self.cards[slot] = Some(card),
+                    None => self.cards.push(Some(card)),
+                }
+                PutCardResponse::new(id, true).to_bytes()
+            }
+            RequestResponseType::DeleteCard => {
+                let r = DeleteCardRequest::from_bytes(buf)?;
+                let slot = self.card_slot(r.card_number);
+                if let Some(slot) = slot {
+                    self.cards[slot] = None;
+                }
+                DeleteCardResponse::new(id, slot.is_some()).to_bytes()
+            }
+            RequestResponseType::DeleteCards => {
+                let r = DeleteCardsRequest::from_bytes(buf)?;
+                let valid = r.magic_word == MAGIC_WORD;
+                if valid {
+                    self.cards.clear();
+                }
+                DeleteCardsResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::GetCards => {
+                GetCardsRequest::from_bytes(buf)?;
+                let records = self.cards.iter().flatten().count() as u32;
+                GetCardsResponse::new(id, records).to_bytes()
+            }
+            RequestResponseType::GetCardByID => {
+                let r = GetCardByIDRequest::from_bytes(buf)?;
+                let card = self.card_slot(r.card_number).and_then(|s| self.cards[s]);
+                match card {
+                    Some(c) => GetCardByIDResponse::new(
+                        id, c.number, c.from, c.to, c.doors[0], c.doors[1], c.doors[2], c.doors[3],
+                        c.pin,
+                    ),
+                    None => GetCardByIDResponse::new(
+                        id,
+                        0,
+                        DateBCD::default(),
+                        DateBCD::default(),
+                        0,
+                        0,
+                        0,
+                        0,
+                        Pin::default(),
+                    ),
+                }
+                .to_bytes()
+            }
+            RequestResponseType::GetCardByIndex => {
+                let r = GetCardByIndexRequest::from_bytes(buf)?;
+                let slot = (r.index as usize)
+                    .checked_sub(1)
+                    .and_then(|i| self.cards.get(i));
+                match slot {
+                    Some(Some(c)) => GetCardByIndexResponse::new(
+                        id, c.number, c.from, c.to, c.doors[0], c.doors[1], c.doors[2], c.doors[3],
+                        c.pin,
+                    ),
+                    Some(None) => GetCardByIndexResponse::new(
+                        id,
+                        DELETED_CARD,
+                        DateBCD::default(),
+                        DateBCD::default(),
+                        0,
+                        0,
+                        0,
+                        0,
+                        Pin::default(),
+                    ),
+                    None => GetCardByIndexResponse::new(
+                        id,
+                        0,
+                        DateBCD::default(),
+                        DateBCD::default(),
+                        0,
+                        0,
+                        0,
+                        0,
+                        Pin::default(),
+                    ),
+                }
+                .to_bytes()
+            }
+            RequestResponseType::SetDoorControlState => {
+                let r = SetDoorControlStateRequest::from_bytes(buf)?;
+                if let Some(state) = door_index(r.door).map(|d| &mut self.door_control[d]) {
+                    *state = (r.control_state, r.delay);
+                }
+                SetDoorControlStateResponse::new(id, r.door, r.control_state, r.delay).to_bytes()
+            }
+            RequestResponseType::GetDoorControlState => {
+                let r = GetDoorControlStateRequest::from_bytes(buf)?;
+                let (mode, delay) = door_index(r.door)
+                    .map(|d| self.door_control[d])
+                    .unwrap_or_default();
+                GetDoorControlStateResponse::new(id, r.door, mode, delay).to_bytes()
+            }
+            RequestResponseType::SetAntiPassback => {
+                let r = SetAntiPassbackRequest::from_bytes(buf)?;
+                let valid = r.antipassback <= 4;
+                if valid {
+                    self.antipassback = r.antipassback;
+                }
+                SetAntiPassbackResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::GetAntiPassback => {
+                GetAntiPassbackRequest::from_bytes(buf)?;
+                GetAntiPassbackResponse::new(id, self.antipassback).to_bytes()
+            }
+            RequestResponseType::SetTimeProfile => {
+                let r = SetTimeProfileRequest::from_bytes(buf)?;
+                // Profiles 0 and 1 are reserved by the firmware.
+                let valid = r.profile_id >= 2;
+                if valid {
+                    self.time_profiles.insert(r.profile_id, r);
+                }
+                SetTimeProfileResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::ClearTimeProfiles => {
+                let r = ClearTimeProfilesRequest::from_bytes(buf)?;
+                if r.magic_word == MAGIC_WORD {
+                    self.time_profiles.clear();
+                }
+                ClearTimeProfilesResponse::new(id, r.magic_word).to_bytes()
+            }
+            RequestResponseType::SetDoorPasscodes => {
+                let r = SetDoorPasscodesRequest::from_bytes(buf)?;
+                let slot = door_index(r.door);
+                if let Some(d) = slot {
+                    self.passcodes[d] = [r.passcode_1, r.passcode_2, r.passcode_3, r.passcode_4];
+                }
+                SetDoorPasscodesResponse::new(id, slot.is_some()).to_bytes()
+            }
+            RequestResponseType::SetRecordSpecialEvents => {
+                let r = SetRecordSpecialEventsRequest::from_bytes(buf)?;
+                self.record_special_events = r.enable;
+                SetRecordSpecialEventsResponse::new(id, true).to_bytes()
+            }
+            RequestResponseType::SetListener => {
+                let r = SetListenerRequest::from_bytes(buf)?;
+                self.listener = (r.ip_address, r.port);
+                SetListenerResponse::new(id, true).to_bytes()
+            }
+            RequestResponseType::GetListener => {
+                GetListenerRequest::from_bytes(buf)?;
+                GetListenerResponse::new(id, self.listener.0, self.listener.1).to_bytes()
+            }
+            RequestResponseType::GetConfig => {
+                GetConfigRequest::from_bytes(buf)?;
+                let [a, b, c, d] = id.to_be_bytes();
+                GetConfigResponse::new(
+                    id,
+                    self.address,
+                    self.subnet,
+                    self.gateway,
+                    MacAddress::new((0x00, 0x66, a, b, c, d)),
+                    Version {
+                        major: 0x08,
+                        minor: 0x92,
+                    },
+                    DateBCD::new(2018, 8, 16),
+                )
+                .to_bytes()
+            }
+            RequestResponseType::SetAddress => {
+                let r = SetAddressRequest::from_bytes(buf)?;
+                if r.magic_word == MAGIC_WORD {
+                    self.address = r.ip_address;
+                    self.subnet = r.subnet;
+                    self.gateway = r.gateway;
+                }
+                // The firmware doesn't reply to SetAddress.
+                return Ok(None);
+            }
+            RequestResponseType::GetTimeProfile => {
+                let r = GetTimeProfileRequest::from_bytes(buf)?;
+                match self.time_profiles.get(&r.profile_id) {
+                    Some(p) => GetTimeProfileResponse::new(
+                        id,
+                        p.profile_id,
+                        p.from,
+                        p.to,
+                        p.monday,
+                        p.tuesday,
+                        p.wednesday,
+                        p.thursday,
+                        p.friday,
+                        p.saturday,
+                        p.sunday,
+                        p.segment1_start,
+                        p.segment1_end,
+                        p.segment2_start,
+                        p.segment2_end,
+                        p.segment3_start,
+                        p.segment3_end,
+                        p.linked_profile_id,
+                    ),
+                    None => GetTimeProfileResponse::new(
+                        id,
+                        0,
+                        DateBCD::default(),
+                        DateBCD::default(),
+                        false,
+                        false,
+                        false,
+                        false,
+                        false,
+                        false,
+                        false,
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        0,
+                    ),
+                }
+                .to_bytes()
+            }
+            RequestResponseType::SetInterlock => {
+                let r = SetInterlockRequest::from_bytes(buf)?;
+                let valid = matches!(r.interlock, 0 | 1 | 2 | 3 | 4 | 8);
+                if valid {
+                    self.interlock = r.interlock;
+                }
+                SetInterlockResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::ActivateKeypads => {
+                let r = ActivateKeypadsRequest::from_bytes(buf)?;
+                self.keypads = [r.reader_1, r.reader_2, r.reader_3, r.reader_4];
+                ActivateKeypadsResponse::new(id, true).to_bytes()
+            }
+            RequestResponseType::ClearTaskList => {
+                let r = ClearTaskListRequest::from_bytes(buf)?;
+                let valid = r.magic_word == MAGIC_WORD;
+                if valid {
+                    self.tasks.clear();
+                }
+                ClearTaskListResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::AddTask => {
+                let r = AddTaskRequest::from_bytes(buf)?;
+                self.tasks.push(r);
+                AddTaskResponse::new(id, true).to_bytes()
+            }
+            RequestResponseType::SetFirstCard => {
+                let r = SetFirstCardRequest::from_bytes(buf)?;
+                let valid = door_index(r.door).is_some();
+                if valid {
+                    self.first_cards.insert(r.door, r);
+                }
+                SetFirstCardResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::RefreshTaskList => {
+                let r = RefreshTaskListRequest::from_bytes(buf)?;
+                RefreshTaskListResponse::new(id, r.magic_word == MAGIC_WORD).to_bytes()
+            }
+            RequestResponseType::GetEvent => {
+                let r = GetEventRequest::from_bytes(buf)?;
+                // Index 0 is the oldest event that hasn't been overwritten, 0xffffffff the newest.
+                let last = self.events.len() as u32;
+                let index = match r.index {
+                    0 if self.overwritten < last => self.overwritten + 1,
+                    0xffffffff => last,
+                    i => i,
+                };
+                let event = (index as usize)
+                    .checked_sub(1)
+                    .and_then(|i| self.events.get(i));
+                match event {
+                    Some(_) if index <= self.overwritten => GetEventResponse::new(
+                        id,
+                        index,
+                        0xff,
+                        false,
+                        0,
+                        0,
+                        0,
+                        DateTime::default(),
+                        0,
+                    ),
+                    Some(e) => GetEventResponse::new(
+                        id,
+                        index,
+                        e.type_,
+                        e.granted,
+                        e.door,
+                        e.direction,
+                        e.card_number,
+                        e.timestamp,
+                        e.reason,
+                    ),
+                    None => GetEventResponse::new(id, 0, 0, false, 0, 0, 0, DateTime::default(), 0),
+                }
+                .to_bytes()
+            }
+            RequestResponseType::SetEventIndex => {
+                let r = SetEventIndexRequest::from_bytes(buf)?;
+                let valid = r.magic_word == MAGIC_WORD;
+                if valid {
+                    self.event_index = r.index;
+                }
+                SetEventIndexResponse::new(id, valid).to_bytes()
+            }
+            RequestResponseType::GetEventIndex => {
+                GetEventIndexRequest::from_bytes(buf)?;
+                GetEventIndexResponse::new(id, self.event_index).to_bytes()
+            }
+        };
+
+        Ok(Some(reply))
+    }
+}
+
+/// Convert a door number (1-4) to an index (0-3).
+fn door_index(door: u8) -> Option<usize> {
+    match door {
+        1..=4 => Some(door as usize - 1),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+use crate::messages::*;
+use crate::{
+    send_and_receive, Card, Device, DeviceConfig, DoorControl, Error, Result, TimeProfile,
+};
+use std::net::{IpAddr, SocketAddr};
+
+/// Time profiles 0 and 1 are reserved by the firmware; user defined profiles are 2-254.
+const TIME_PROFILES: std::ops::RangeInclusive<u8> = 2..=254;
+
+/// Everything that can be read from a [`Device`], created with [`Device::snapshot`] and
+/// replayed onto a (replacement) device with [`Device::restore`].
+///
+/// With the `serde` feature enabled, a [`DeviceSnapshot`] can be stored as JSON, TOML or any
+/// other format supported by serde.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceSnapshot {
+    /// Whether special events are recorded. The firmware doesn't report this setting, so
+    /// [`Device::snapshot`] leaves it `None`; set it to have [`Device::restore`] apply it.
+    pub record_special_events: Option<bool>,
+    pub listener: SocketAddr,
+    pub config: DeviceConfig,
+    /// [`DoorControl`] of doors 1-4.
+    pub doors: Vec<DoorControl>,
+    pub time_profiles: Vec<TimeProfile>,
+    pub cards: Vec<Card>,
+}
+
+impl Device<'_> {
+    /// Read the configuration, listener, door control states, time profiles and cards of the
+    /// [`Device`] into a [`DeviceSnapshot`].
+    pub fn snapshot(&self) -> Result<DeviceSnapshot> {
+        let mut time_profiles = Vec::new();
+        for id in TIME_PROFILES {
+            let request = GetTimeProfileRequest::new(self.id, id);
+            let response: GetTimeProfileResponse = send_and_receive(request, self)?;
+            // The firmware reports undefined profiles with a profile ID of 0.
+            if response.profile_id == id {
+                time_profiles.push(response.try_into()?);
+            }
+        }
+
+        Ok(DeviceSnapshot {
+            record_special_events: None,
+            listener: self.get_listener()?,
+            config: self.get_config()?,
+            doors: (1..=4)
+                .map(|door| self.get_door_control(door))
+                .collect::<Result<_>>()?,
+            time_profiles,
+            cards: self.get_all_cards()?,
+        })
+    }
+
+    /// Replay a [`DeviceSnapshot`] onto the [`Device`], which doesn't need to be the device the
+    /// snapshot was taken from. Time profiles and cards are replaced by the ones in the snapshot.
+    ///
+    /// The network configuration is not restored, since the original device may still be
+    /// reachable on that address. Use [`Device::set_network_config`] with
+    /// [`DeviceSnapshot::config`] to move the device to it.
+    ///
+    /// Example:
+    /// ```no_run
+    /// use uhppote_rs::Uhppoted;
+    /// let uhppoted = Uhppoted::default();
+    /// let snapshot = uhppoted.get_device(423196779, None).snapshot().unwrap();
+    /// uhppoted.get_device(405419896, None).restore(&snapshot).unwrap();
+    /// ```
+    pub fn restore(&self, snapshot: &DeviceSnapshot) -> Result<()> {
+        match snapshot.listener.ip() {
+            IpAddr::V4(ip) => self.set_listener(ip, snapshot.listener.port())?,
+            IpAddr::V6(ip) => {
+                return Err(Error::InvalidArgument(format!(
+                    "listener must be an IPv4 address, got {}",
+                    ip
+                )))
+            }
+        }
+
+        for (door, state) in (1..=4).zip(&snapshot.doors) {
+            self.set_door_control_state(door, *state)?;
+        }
+
+        if let Some(enable) = snapshot.record_special_events {
+            self.enable_record_special_events(enable)?;
+        }
+
+        self.clear_time_profiles()?;
+        for profile in &snapshot.time_profiles {
+            self.add_or_update_time_profile(profile.clone())?;
+        }
+
+        self.sync_cards(snapshot.cards.clone())?
+            .into_iter()
+            .try_for_each(|r| r.result)
+    }
+}
@@ -0,0 +1,250 @@
+//! The ways requests can reach devices.
+use crate::dispatcher::Dispatcher;
+use crate::{Result, UHPPOTE_PORT};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How requests reach a device and how replies come back.
+///
+/// Messages are always 64 byte frames. A [`Transport`] only moves them; matching replies to
+/// requests, timeouts per attempt, retries and metrics are handled by [`Uhppoted`](crate::Uhppoted)
+/// on top of it. Use [`Uhppoted::transport`](crate::Uhppoted::transport) to use a [`Transport`] for
+/// all devices, or [`Device::transport`](crate::Device::transport) for a single device.
+///
+/// This crate ships [`UdpTransport`] (the default), [`TcpTransport`] and [`MockTransport`].
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Send `request`. When `timeout` is `Some`, hand every datagram received in reply to `reply`
+    /// until it returns `true` or `timeout` expires. Not receiving any reply is not an error.
+    fn exchange(
+        &self,
+        request: &[u8; 64],
+        timeout: Option<Duration>,
+        reply: &mut dyn FnMut(&[u8]) -> bool,
+    ) -> Result<()>;
+}
+
+/// Sends requests as UDP datagrams to a single device or to a broadcast address.
+///
+/// All [`UdpTransport`]s created by the same [`Uhppoted`](crate::Uhppoted) share a single socket,
+/// see [`Uhppoted::new`](crate::Uhppoted::new). A [`UdpTransport`] created with
+/// [`UdpTransport::unicast`] or [`UdpTransport::broadcast`] has a socket of its own.
+#[derive(Debug, Clone)]
+pub struct UdpTransport {
+    dispatcher: Arc<Dispatcher>,
+    address: SocketAddr,
+}
+
+impl UdpTransport {
+    /// Send requests from `bind` to the device at `address`, usually port 60000 of the device.
+    pub fn unicast(bind: SocketAddr, address: SocketAddr) -> Result<UdpTransport> {
+        Ok(UdpTransport {
+            dispatcher: Arc::new(Dispatcher::bind(bind)?),
+            address,
+        })
+    }
+
+    /// Send requests from `bind` to port 60000 of `broadcast`, for instance `255.255.255.255`,
+    /// so they reach all devices on the local network.
+    pub fn broadcast(bind: SocketAddr, broadcast: Ipv4Addr) -> Result<UdpTransport> {
+        UdpTransport::unicast(bind, SocketAddr::from((broadcast, UHPPOTE_PORT)))
+    }
+
+    /// A [`UdpTransport`] on an existing [`Dispatcher`].
+    pub(crate) fn with_dispatcher(dispatcher: Arc<Dispatcher>, address: SocketAddr) -> Self {
+        UdpTransport {
+            dispatcher,
+            address,
+        }
+    }
+}
+
+impl Transport for UdpTransport {
+    fn exchange(
+        &self,
+        request: &[u8; 64],
+        timeout: Option<Duration>,
+        reply: &mut dyn FnMut(&[u8]) -> bool,
+    ) -> Result<()> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return self.dispatcher.send_to(request, self.address),
+        };
+        let deadline = Instant::now() + timeout;
+        let device_id = u32::from_le_bytes([request[4], request[5], request[6], request[7]]);
+        let replies = self.dispatcher.subscribe(device_id, request[1], timeout)?;
+        self.dispatcher.send_to(request, self.address)?;
+
+        while let Some(datagram) =
+            replies.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            if reply(&datagram) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sends requests over a TCP connection to a single device, which newer firmware accepts on
+/// port 60000. The connection is opened on first use and reopened after an error. Requests on
+/// the same [`TcpTransport`] are sent one at a time.
+#[derive(Debug)]
+pub struct TcpTransport {
+    address: SocketAddr,
+    stream: Mutex<Option<TcpStream>>,
+}
+
+impl TcpTransport {
+    /// Connect to the device at `address`, usually port 60000 of the device.
+    pub fn new(address: SocketAddr) -> TcpTransport {
+        TcpTransport {
+            address,
+            stream: Mutex::new(None),
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn exchange(
+        &self,
+        request: &[u8; 64],
+        timeout: Option<Duration>,
+        reply: &mut dyn FnMut(&[u8]) -> bool,
+    ) -> Result<()> {
+        let mut stream = self.stream.lock().unwrap_or_else(|e| e.into_inner());
+        let connect_timeout = timeout.unwrap_or(Duration::new(5, 0));
+        let s = match &mut *stream {
+            Some(s) => s,
+            None => stream.insert(TcpStream::connect_timeout(&self.address, connect_timeout)?),
+        };
+        match exchange_frames(s, request, timeout, reply) {
+            Ok(true) => Ok(()),
+            // Without an accepted reply, a late reply may still arrive and would be taken for the
+            // reply to the next request. An error may leave the connection out of step with the
+            // device as well. Either way, start over on the next request.
+            Ok(false) => {
+                *stream = None;
+                Ok(())
+            }
+            Err(e) => {
+                *stream = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Send `request` and hand the frames received in reply to `reply`. Returns whether the
+/// connection is still in step: no reply was expected, or `reply` accepted one.
+fn exchange_frames(
+    stream: &mut TcpStream,
+    request: &[u8; 64],
+    timeout: Option<Duration>,
+    reply: &mut dyn FnMut(&[u8]) -> bool,
+) -> Result<bool> {
+    stream.write_all(request)?;
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return Ok(true),
+    };
+    let deadline = Instant::now() + timeout;
+    let mut frame = [0u8; 64];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(false);
+        }
+        stream.set_read_timeout(Some(remaining))?;
+        match stream.read_exact(&mut frame) {
+            Ok(()) if reply(&frame) => return Ok(true),
+            Ok(()) => {}
+            // Includes a frame that only arrived partially.
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(false)
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Function answering the requests sent to a [`MockTransport`].
+type Handler = dyn Fn(&[u8; 64]) -> Vec<Vec<u8>> + Send + Sync;
+
+/// An in-memory [`Transport`] for tests: every request is answered by a function and recorded.
+///
+/// Example:
+/// ```
+/// use std::sync::Arc;
+/// use uhppote_rs::{MockTransport, Uhppoted};
+/// let transport = Arc::new(MockTransport::new(|_request| Vec::new()));
+/// let uhppoted = Uhppoted::default().transport(transport.clone());
+/// let device = uhppoted.get_device(423196779, None);
+/// assert!(device.get_status().is_err());
+/// // Reads are retried, see RetryPolicy.
+/// assert_eq!(transport.requests().len(), 3);
+/// ```
+pub struct MockTransport {
+    handler: Box<Handler>,
+    requests: Mutex<Vec<[u8; 64]>>,
+}
+
+impl MockTransport {
+    /// Create a [`MockTransport`] that replies to every request with the datagrams returned by
+    /// `handler`.
+    pub fn new<F>(handler: F) -> MockTransport
+    where
+        F: Fn(&[u8; 64]) -> Vec<Vec<u8>> + Send + Sync + 'static,
+    {
+        MockTransport {
+            handler: Box::new(handler),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// All requests sent so far.
+    pub fn requests(&self) -> Vec<[u8; 64]> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl fmt::Debug for MockTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockTransport")
+            .field("requests", &self.requests)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for MockTransport {
+    fn exchange(
+        &self,
+        request: &[u8; 64],
+        timeout: Option<Duration>,
+        reply: &mut dyn FnMut(&[u8]) -> bool,
+    ) -> Result<()> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(*request);
+        let replies = (self.handler)(request);
+        if timeout.is_some() {
+            for datagram in replies {
+                if reply(&datagram) {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use crate::Error;
+
+/// Anti-passback mode of a [`Device`]. Doors in the same group (in parentheses) are paired: a
+/// card that entered through one door of a pair has to leave through the other before it is
+/// granted access again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AntiPassback {
+    /// Anti-passback is disabled.
+    Disabled = 0,
+    /// Doors 1 and 2 are paired, as are doors 3 and 4: `(1:2);(3:4)`.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_12_34"))]
+    Doors12And34 = 1,
+    /// Doors 1 and 3 are paired with doors 2 and 4: `(1,3):(2,4)`.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_13_24"))]
+    Doors13And24 = 2,
+    /// Door 1 is paired with doors 2 and 3: `1:(2,3)`.
+    #[cfg_attr(feature = "serde", serde(rename = "door_1_23"))]
+    Door1And23 = 3,
+    /// Door 1 is paired with doors 2, 3 and 4: `1:(2,3,4)`.
+    #[cfg_attr(feature = "serde", serde(rename = "door_1_234"))]
+    Door1And234 = 4,
+}
+
+impl TryFrom<u8> for AntiPassback {
+    type Error = Error;
+
+    fn try_from(mode: u8) -> Result<AntiPassback, Error> {
+        match mode {
+            0 => Ok(AntiPassback::Disabled),
+            1 => Ok(AntiPassback::Doors12And34),
+            2 => Ok(AntiPassback::Doors13And24),
+            3 => Ok(AntiPassback::Door1And23),
+            4 => Ok(AntiPassback::Door1And234),
+            _ => Err(Error::Decode(format!(
+                "unknown anti-passback mode: {}",
+                mode
+            ))),
+        }
+    }
+}
@@ -1,18 +1,21 @@
 use chrono::NaiveDate;
 
-use crate::messages::{GetCardByIndexResponse, GetCardByIDResponse};
-use anyhow::Result;
+use crate::messages::{GetCardByIDResponse, GetCardByIndexResponse};
+use crate::{Error, Result};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
     pub number: u32,
     pub from: NaiveDate,
     pub to: NaiveDate,
     pub doors: Vec<u8>,
+    /// PIN used by doors that require a card and a PIN. `None` if the card has no PIN.
+    pub pin: Option<u32>,
 }
 
 impl TryFrom<GetCardByIndexResponse> for Card {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(response: GetCardByIndexResponse) -> Result<Card> {
         Ok(Card {
             number: response.card_number,
@@ -24,12 +27,13 @@ impl TryFrom<GetCardByIndexResponse> for Card {
                 response.door_3,
                 response.door_4,
             ],
+            pin: response.pin.into(),
         })
     }
 }
 
 impl TryFrom<GetCardByIDResponse> for Card {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(response: GetCardByIDResponse) -> Result<Card> {
         Ok(Card {
             number: response.card_number,
@@ -41,6 +45,7 @@ impl TryFrom<GetCardByIDResponse> for Card {
                 response.door_3,
                 response.door_4,
             ],
+            pin: response.pin.into(),
         })
     }
 }
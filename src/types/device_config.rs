@@ -3,9 +3,11 @@ use std::net::Ipv4Addr;
 use chrono::NaiveDate;
 
 use crate::messages::GetConfigResponse;
+use crate::Error;
 
 /// Configuration of a [`Device`]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceConfig {
     pub id: u32,
     pub address: Ipv4Addr,
@@ -17,7 +19,7 @@ pub struct DeviceConfig {
 }
 
 impl TryFrom<GetConfigResponse> for DeviceConfig {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(response: GetConfigResponse) -> Result<Self, Self::Error> {
         Ok(DeviceConfig {
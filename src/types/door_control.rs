@@ -3,9 +3,12 @@ use std::time::Duration;
 use crate::messages::GetDoorControlStateResponse;
 use crate::messages::SetDoorControlStateResponse;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DoorControl {
     pub mode: DoorControlMode,
+    /// How long the door stays unlocked, in whole seconds. Serialized as seconds.
+    #[cfg_attr(feature = "serde", serde(with = "crate::seconds"))]
     pub delay: Duration,
 }
 
@@ -27,7 +30,9 @@ impl From<SetDoorControlStateResponse> for DoorControl {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum DoorControlMode {
     NormallyOpen = 1,
     NormallyClosed = 2,
@@ -2,10 +2,11 @@ use chrono::NaiveDateTime;
 
 use super::direction::Direction;
 use crate::messages::GetEventResponse;
-use anyhow::Result;
+use crate::{Error, Result};
 
 /// Event that occurred on a [`Device`]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event {
     pub timestamp: NaiveDateTime,
     pub index: u32,
@@ -18,7 +19,7 @@ pub struct Event {
 }
 
 impl TryFrom<GetEventResponse> for Event {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(response: GetEventResponse) -> Result<Self> {
         Ok(Event {
             timestamp: response.timestamp.try_into()?,
@@ -33,7 +34,9 @@ impl TryFrom<GetEventResponse> for Event {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum EventType {
     None = 0,
     Swipe = 1,
@@ -55,7 +58,9 @@ impl From<u8> for EventType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum EventReason {
     None = 0,
     Swipe = 1,
@@ -0,0 +1,22 @@
+use chrono::NaiveTime;
+
+use super::door_control::DoorControlMode;
+
+/// First card configuration of a door. Between `start` and `end` the door is switched to
+/// `start_mode` once the first valid card is swiped and switched to `end_mode` at `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirstCardConfig {
+    pub door: u8,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub start_mode: DoorControlMode,
+    pub end_mode: DoorControlMode,
+    pub monday: bool,
+    pub tuesday: bool,
+    pub wednesday: bool,
+    pub thursday: bool,
+    pub friday: bool,
+    pub saturday: bool,
+    pub sunday: bool,
+}
@@ -0,0 +1,24 @@
+/// Door interlock configuration of a [`Device`]. Doors in an interlocked group can only be opened
+/// one at a time: a door won't open while another door of its group is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Interlock {
+    /// No doors are interlocked.
+    None = 0,
+    /// Doors 1 and 2 are interlocked.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_12"))]
+    Doors12 = 1,
+    /// Doors 3 and 4 are interlocked.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_34"))]
+    Doors34 = 2,
+    /// Doors 1 and 2 are interlocked, as are doors 3 and 4.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_12_34"))]
+    Doors12And34 = 3,
+    /// Doors 1, 2 and 3 are interlocked.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_123"))]
+    Doors123 = 4,
+    /// Doors 1, 2, 3 and 4 are interlocked.
+    #[cfg_attr(feature = "serde", serde(rename = "doors_1234"))]
+    Doors1234 = 8,
+}
@@ -1,16 +1,22 @@
+mod antipassback;
 mod card;
 mod device_config;
 mod direction;
 mod door_control;
 mod event;
+mod first_card;
+mod interlock;
 mod status;
 mod task;
 mod time_profile;
+pub use antipassback::*;
 pub use card::*;
 pub use device_config::*;
 pub use direction::*;
 pub use door_control::*;
 pub use event::*;
+pub use first_card::*;
+pub use interlock::*;
 pub use status::*;
 pub use task::*;
 pub use time_profile::*;
@@ -2,10 +2,11 @@ use chrono::{NaiveDate, NaiveTime};
 
 use super::event::Event;
 use crate::messages::GetStatusResponse;
-use anyhow::Result;
+use crate::{Error, Result};
 
 /// Status of a [`Device`]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Status {
     pub device_id: u32,
     pub system_time: NaiveTime,
@@ -21,7 +22,7 @@ pub struct Status {
 }
 
 impl TryFrom<GetStatusResponse> for Status {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(response: GetStatusResponse) -> Result<Self> {
         let event = match response.event_index {
             0 => None,
@@ -1,6 +1,7 @@
 use chrono::{NaiveDate, NaiveTime};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Task {
     pub task: TaskID,
     pub door: u8,
@@ -17,8 +18,10 @@ pub struct Task {
     pub more_cards: u8,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TaskID {
     ControlDoor = 1,
     UnlockDoor = 2,
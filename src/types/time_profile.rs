@@ -1,8 +1,9 @@
 use crate::messages::GetTimeProfileResponse;
-use anyhow::Result;
+use crate::{Error, Result};
 use chrono::{NaiveDate, NaiveTime};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeProfile {
     pub id: u8,
     pub linked_profile_id: u8,
@@ -19,11 +20,11 @@ pub struct TimeProfile {
 }
 
 impl TryFrom<GetTimeProfileResponse> for TimeProfile {
-    type Error = anyhow::Error;
+    type Error = Error;
     fn try_from(response: GetTimeProfileResponse) -> Result<Self> {
         let mut segments = [TimeProfileSegment {
-            start: NaiveTime::from_hms(0, 0, 0),
-            end: NaiveTime::from_hms(0, 0, 0),
+            start: NaiveTime::MIN,
+            end: NaiveTime::MIN,
         }; 3];
 
         segments[0] = TimeProfileSegment {
@@ -56,7 +57,8 @@ impl TryFrom<GetTimeProfileResponse> for TimeProfile {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeProfileSegment {
     pub start: NaiveTime,
     pub end: NaiveTime,
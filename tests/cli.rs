@@ -0,0 +1,121 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::Command;
+
+use serde_json::{json, Value};
+use uhppote_rs::simulator::Simulator;
+
+const DEVICE_ID: u32 = 423196779;
+
+fn uhppote(ip: Ipv4Addr, args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_uhppote"))
+        .arg("--ip")
+        .arg(ip.to_string())
+        .arg("--timeout")
+        .arg("1")
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    (output.status.success(), stdout)
+}
+
+fn json(ip: Ipv4Addr, args: &[&str]) -> Value {
+    let mut all = vec!["--json"];
+    all.extend_from_slice(args);
+    let (success, stdout) = uhppote(ip, &all);
+    assert!(success, "{:?} failed", args);
+    serde_json::from_str(&stdout).unwrap()
+}
+
+#[test]
+fn cards() {
+    let ip = Ipv4Addr::new(127, 0, 0, 41);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let id = DEVICE_ID.to_string();
+
+    let put = [
+        "put-card",
+        &id,
+        "8165537",
+        "--from",
+        "2023-01-01",
+        "--to",
+        "2023-12-31",
+        "--doors",
+        "1,0,29,1",
+        "--pin",
+        "7531",
+    ];
+    assert_eq!(json(ip, &put), json!("ok"));
+    assert_eq!(
+        json(ip, &["get-card", &id, "8165537"]),
+        json!({
+            "number": 8165537,
+            "from": "2023-01-01",
+            "to": "2023-12-31",
+            "doors": [1, 0, 29, 1],
+            "pin": 7531,
+        })
+    );
+
+    let (success, table) = uhppote(ip, &["list-cards", &id]);
+    assert!(success);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("doors"));
+    assert!(lines[1].contains("1,0,29,1"));
+
+    assert_eq!(json(ip, &["delete-card", &id, "8165537"]), json!("ok"));
+    assert_eq!(json(ip, &["list-cards", &id]), json!([]));
+}
+
+#[test]
+fn door_control_and_listener() {
+    let ip = Ipv4Addr::new(127, 0, 0, 42);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let id = DEVICE_ID.to_string();
+
+    assert_eq!(
+        json(ip, &["set-door-control", &id, "2", "normally_closed", "7"]),
+        json!({ "mode": "normally_closed", "delay": 7 })
+    );
+    assert_eq!(
+        json(ip, &["set-listener", &id, "192.168.1.10:60001"]),
+        json!("ok")
+    );
+    assert_eq!(
+        json(ip, &["get-listener", &id]),
+        json!("192.168.1.10:60001")
+    );
+}
+
+#[test]
+fn errors() {
+    let ip = Ipv4Addr::new(127, 0, 0, 43);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let (success, _) = uhppote(ip, &["get-status", "405419896"]);
+    assert!(!success);
+}
+
+#[test]
+fn discover() {
+    let ip = Ipv4Addr::new(127, 0, 0, 44);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let range = format!("{}-{}", ip, ip);
+    let found = json(
+        ip,
+        &["--bind", "127.0.0.1:0", "discover", "--range", &range],
+    );
+    let found: Vec<&Value> = found
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|d| d["address"] == json!(ip))
+        .collect();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["id"], DEVICE_ID);
+    assert_eq!(found[0]["local_address"], "127.0.0.1");
+
+    let (success, _) = uhppote(ip, &["discover", "--range", "10.0.0.1-nowhere"]);
+    assert!(!success);
+}
@@ -0,0 +1,20 @@
+//! Fixtures shared by the integration tests.
+use chrono::NaiveDate;
+use uhppote_rs::*;
+
+/// A granted swipe of `card_number` at door 1.
+pub fn swipe(card_number: u32) -> Event {
+    Event {
+        timestamp: NaiveDate::from_ymd_opt(2023, 5, 4)
+            .unwrap()
+            .and_hms_opt(12, 34, 56)
+            .unwrap(),
+        index: 0,
+        event_type: EventType::Swipe,
+        granted: true,
+        door: 1,
+        direction: Direction::In,
+        card_number,
+        reason: EventReason::Swipe,
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+const DEVICE_ID: u32 = 423196779;
+const MISSING_ID: u32 = 405419896;
+
+fn get(address: SocketAddr, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+/// The value of the sample with exactly `name` and `labels`.
+fn value(metrics: &str, name: &str, labels: &str) -> f64 {
+    let prefix = format!("{}{{{}}} ", name, labels);
+    metrics
+        .lines()
+        .find_map(|l| l.strip_prefix(&prefix))
+        .unwrap_or_else(|| panic!("no {} in:\n{}", prefix, metrics))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn exports_device_and_client_metrics() {
+    let ip = Ipv4Addr::new(127, 0, 0, 81);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+
+    let uhppoted = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        Duration::from_millis(200),
+    )
+    .retry_policy(RetryPolicy {
+        attempts: 1,
+        ..Default::default()
+    });
+    let exporter = MetricsExporter::bind(
+        uhppoted,
+        vec![
+            (DEVICE_ID, Some(ip)),
+            (MISSING_ID, Some(Ipv4Addr::new(127, 0, 0, 82))),
+        ],
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+    let address = exporter.local_addr().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        std::thread::spawn(move || exporter.run(&stop).unwrap())
+    };
+
+    let (status, metrics) = get(address, "/metrics");
+    assert_eq!(status, 200);
+    assert_eq!(
+        value(&metrics, "uhppote_up", "device_id=\"423196779\""),
+        1.0
+    );
+    assert_eq!(
+        value(&metrics, "uhppote_up", "device_id=\"405419896\""),
+        0.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "uhppote_door_open",
+            "device_id=\"423196779\",door=\"1\""
+        ),
+        0.0
+    );
+    assert!(
+        value(
+            &metrics,
+            "uhppote_clock_drift_seconds",
+            "device_id=\"423196779\""
+        )
+        .abs()
+            < 5.0
+    );
+    assert!(!metrics.contains("uhppote_event_index{device_id=\"405419896\"}"));
+
+    assert_eq!(
+        value(
+            &metrics,
+            "uhppote_client_requests_total",
+            "type=\"GetStatus\""
+        ),
+        2.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "uhppote_client_timeouts_total",
+            "type=\"GetStatus\""
+        ),
+        1.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "uhppote_client_request_duration_seconds_count",
+            "type=\"GetTime\""
+        ),
+        1.0
+    );
+
+    let (status, _) = get(address, "/other");
+    assert_eq!(status, 404);
+
+    stop.store(true, Ordering::Relaxed);
+    thread.join().unwrap();
+}
@@ -0,0 +1,179 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+mod common;
+use common::swipe;
+
+const DEVICE_ID: u32 = 423196779;
+
+/// Wait until `path` has `n` lines and return them.
+fn lines(path: &std::path::Path, n: usize) -> Vec<Value> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        let lines: Vec<Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        if lines.len() >= n || Instant::now() > deadline {
+            return lines;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn forwards_and_backfills_events() {
+    let ip = Ipv4Addr::new(127, 0, 0, 51);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let dir = std::env::temp_dir().join(format!("uhppote-forwarder-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("events.jsonl");
+
+    let listener: SocketAddr = "127.0.0.1:61051".parse().unwrap();
+    let config = ForwarderConfig {
+        bind: listener,
+        listener,
+        // Nothing answers at 127.0.0.53, which must not keep the other device from being
+        // forwarded.
+        devices: vec![
+            (DEVICE_ID, Some(ip)),
+            (1, Some(Ipv4Addr::new(127, 0, 0, 53))),
+        ],
+        output: Output::File {
+            path: path.clone(),
+            max_bytes: 1 << 20,
+            keep: 1,
+        },
+    };
+    let uhppoted = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        Duration::from_millis(500),
+    );
+    let (errors_tx, errors) = std::sync::mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            EventForwarder::new(uhppoted, config)
+                .on_error(move |id, e| errors_tx.send((id, e.to_string())).unwrap())
+                .run(&stop)
+                .unwrap()
+        })
+    };
+
+    // Wait for the forwarder to register as the listener.
+    let u = Uhppoted::default();
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while device.get_listener().unwrap() != listener {
+        assert!(Instant::now() < deadline);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let (id, _) = errors.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(id, 1);
+
+    simulator.add_event(swipe(1)).unwrap();
+    assert_eq!(lines(&path, 1).len(), 1);
+
+    // Events 2 and 3 are not delivered, so they are backfilled when event 4 arrives.
+    device.set_listener(Ipv4Addr::UNSPECIFIED, 0).unwrap();
+    simulator.add_event(swipe(2)).unwrap();
+    simulator.add_event(swipe(3)).unwrap();
+    device
+        .set_listener(Ipv4Addr::LOCALHOST, listener.port())
+        .unwrap();
+    simulator.add_event(swipe(4)).unwrap();
+
+    let lines = lines(&path, 4);
+    stop.store(true, Ordering::Relaxed);
+    thread.join().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let cards: Vec<u64> = lines
+        .iter()
+        .map(|l| l["card_number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(cards, vec![1, 2, 3, 4]);
+    assert!(lines.iter().all(|l| l["device_id"] == DEVICE_ID));
+    assert!(lines.iter().all(|l| l["kind"] == "event"));
+}
+
+#[test]
+fn skips_events_that_cannot_be_read() {
+    let ip = Ipv4Addr::new(127, 0, 0, 52);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let dir = std::env::temp_dir().join(format!("uhppote-forwarder-skip-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("events.jsonl");
+
+    let listener: SocketAddr = "127.0.0.1:61052".parse().unwrap();
+    let config = ForwarderConfig {
+        bind: listener,
+        listener,
+        devices: vec![(DEVICE_ID, Some(ip))],
+        output: Output::File {
+            path: path.clone(),
+            max_bytes: 1 << 20,
+            keep: 1,
+        },
+    };
+    let (errors_tx, errors) = std::sync::mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            EventForwarder::new(Uhppoted::default(), config)
+                .on_error(move |id, e| errors_tx.send((id, e.to_string())).unwrap())
+                .run(&stop)
+                .unwrap()
+        })
+    };
+
+    let u = Uhppoted::default();
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while device.get_listener().unwrap() != listener {
+        assert!(Instant::now() < deadline);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    simulator.add_event(swipe(1)).unwrap();
+    assert_eq!(lines(&path, 1).len(), 1);
+
+    // Event 2 can't be decoded, so it is skipped while events 3 and 4 are backfilled.
+    device.set_listener(Ipv4Addr::UNSPECIFIED, 0).unwrap();
+    simulator.add_event(swipe(2)).unwrap();
+    simulator.add_event(swipe(3)).unwrap();
+    simulator.corrupt_event(2);
+    device
+        .set_listener(Ipv4Addr::LOCALHOST, listener.port())
+        .unwrap();
+    simulator.add_event(swipe(4)).unwrap();
+    assert_eq!(lines(&path, 4).len(), 4);
+    simulator.add_event(swipe(5)).unwrap();
+
+    let lines = lines(&path, 5);
+    stop.store(true, Ordering::Relaxed);
+    thread.join().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(lines[1]["kind"], "missing");
+    assert_eq!(lines[1]["from"], 2);
+    let cards: Vec<u64> = lines
+        .iter()
+        .filter_map(|l| l["card_number"].as_u64())
+        .collect();
+    assert_eq!(cards, vec![1, 3, 4, 5]);
+    let (id, _) = errors.try_recv().unwrap();
+    assert_eq!(id, DEVICE_ID);
+    assert!(errors.try_recv().is_err());
+}
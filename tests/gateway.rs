@@ -0,0 +1,237 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+mod common;
+use common::swipe;
+
+const DEVICE_ID: u32 = 423196779;
+
+struct Gateway {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Gateway {
+    fn start(ip: Ipv4Addr) -> Gateway {
+        Gateway::with_devices(vec![(DEVICE_ID, Some(ip))])
+    }
+
+    fn with_devices(devices: Vec<(u32, Option<Ipv4Addr>)>) -> Gateway {
+        Gateway::serve(
+            HttpGateway::bind(Uhppoted::default(), devices, "127.0.0.1:0".parse().unwrap())
+                .unwrap(),
+        )
+    }
+
+    fn serve(gateway: HttpGateway) -> Gateway {
+        let address = gateway.local_addr().unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::spawn(move || gateway.run(&stop).unwrap())
+        };
+        Gateway {
+            address,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let mut stream = TcpStream::connect(self.address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+}
+
+impl Drop for Gateway {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+#[test]
+fn status_and_doors() {
+    let ip = Ipv4Addr::new(127, 0, 0, 61);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let gateway = Gateway::start(ip);
+
+    let (status, body) = gateway.request("GET", "/devices/423196779/status", None);
+    assert_eq!(status, 200);
+    assert_eq!(body["device_id"], DEVICE_ID);
+
+    let (status, body) = gateway.request("POST", "/devices/423196779/doors/1/open", None);
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({ "ok": true }));
+
+    let door = json!({ "mode": "normally_open", "delay": 7 });
+    let (status, _) = gateway.request("PUT", "/devices/423196779/doors/2", Some(door.clone()));
+    assert_eq!(status, 200);
+    let (status, body) = gateway.request("GET", "/devices/423196779/doors/2", None);
+    assert_eq!(status, 200);
+    assert_eq!(body, door);
+}
+
+#[test]
+fn cards_and_events() {
+    let ip = Ipv4Addr::new(127, 0, 0, 62);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let gateway = Gateway::start(ip);
+
+    let card = json!({
+        "number": 12345,
+        "from": "2023-01-01",
+        "to": "2023-12-31",
+        "doors": [1, 0, 29, 1],
+        "pin": null,
+    });
+    let (status, _) = gateway.request("PUT", "/devices/423196779/cards/12345", Some(card.clone()));
+    assert_eq!(status, 200);
+    let (status, body) = gateway.request("GET", "/devices/423196779/cards/12345", None);
+    assert_eq!(status, 200);
+    assert_eq!(body, card);
+    let (_, body) = gateway.request("GET", "/devices/423196779/cards", None);
+    assert_eq!(body, json!([card]));
+
+    let (status, _) = gateway.request("DELETE", "/devices/423196779/cards/12345", None);
+    assert_eq!(status, 200);
+    let (_, body) = gateway.request("GET", "/devices/423196779/cards", None);
+    assert_eq!(body, json!([]));
+
+    for card_number in [1, 2, 3] {
+        simulator.add_event(swipe(card_number)).unwrap();
+    }
+    let (status, body) = gateway.request("GET", "/devices/423196779/events?from=1", None);
+    assert_eq!(status, 200);
+    let cards: Vec<u64> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["card_number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(cards, vec![2, 3]);
+}
+
+#[test]
+fn errors() {
+    let ip = Ipv4Addr::new(127, 0, 0, 63);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let gateway = Gateway::start(ip);
+
+    let (status, body) = gateway.request("GET", "/nothing", None);
+    assert_eq!(status, 404);
+    assert!(body["error"].is_string());
+
+    let (status, _) = gateway.request("POST", "/devices/423196779/status", None);
+    assert_eq!(status, 405);
+
+    let (status, _) = gateway.request("GET", "/devices/not-a-number/status", None);
+    assert_eq!(status, 400);
+
+    let (status, _) = gateway.request("PUT", "/devices/423196779/cards/1", Some(json!({})));
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn slow_devices_do_not_hold_up_others() {
+    let ip = Ipv4Addr::new(127, 0, 0, 64);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    // Nothing answers at 127.0.0.65, so requests for device 1 wait for the full timeout.
+    let gateway = Arc::new(Gateway::with_devices(vec![
+        (DEVICE_ID, Some(ip)),
+        (1, Some(Ipv4Addr::new(127, 0, 0, 65))),
+    ]));
+
+    let slow = {
+        let gateway = gateway.clone();
+        std::thread::spawn(move || gateway.request("GET", "/devices/1/status", None))
+    };
+    std::thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    let (status, _) = gateway.request("GET", "/devices/423196779/status", None);
+    assert_eq!(status, 200);
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let (status, _) = slow.join().unwrap();
+    assert_eq!(status, 504);
+}
+
+#[test]
+fn too_many_requests() {
+    // Nothing answers at 127.0.0.66, so the first request holds the only slot.
+    let uhppoted = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        Duration::from_millis(500),
+    );
+    let gateway = HttpGateway::bind(
+        uhppoted,
+        vec![(1, Some(Ipv4Addr::new(127, 0, 0, 66)))],
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap()
+    .max_requests(1);
+    let gateway = Arc::new(Gateway::serve(gateway));
+
+    let slow = {
+        let gateway = gateway.clone();
+        std::thread::spawn(move || gateway.request("GET", "/devices/1/status", None))
+    };
+    std::thread::sleep(Duration::from_millis(200));
+    let (status, body) = gateway.request("GET", "/devices/1/status", None);
+    assert_eq!(status, 503);
+    assert!(body["error"].is_string());
+
+    let (status, _) = slow.join().unwrap();
+    assert_eq!(status, 504);
+    let (status, _) = gateway.request("GET", "/nothing", None);
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn registered_controllers_take_precedence() {
+    let ip = Ipv4Addr::new(127, 0, 0, 67);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60005))).unwrap();
+    let uhppoted = Uhppoted::default().controllers([Controller {
+        port: 60005,
+        ..Controller::new(DEVICE_ID, ip)
+    }]);
+    // Nothing answers at the listed address.
+    let gateway = HttpGateway::bind(
+        uhppoted,
+        vec![(DEVICE_ID, Some(Ipv4Addr::new(127, 0, 0, 68)))],
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+    let gateway = Gateway::serve(gateway);
+
+    let (status, body) = gateway.request("GET", "/devices/423196779/status", None);
+    assert_eq!(status, 200);
+    assert_eq!(body["device_id"], DEVICE_ID);
+}
@@ -0,0 +1,270 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+mod common;
+use common::swipe;
+
+const DEVICE_ID: u32 = 423196779;
+
+/// A minimal MQTT 3.1.1 broker: it acknowledges everything, delivers messages with QoS 0 and
+/// reports every message published by a client.
+struct Broker {
+    address: SocketAddr,
+    subscriptions: Arc<Mutex<Vec<(String, TcpStream)>>>,
+    published: Receiver<(String, Value)>,
+}
+
+impl Broker {
+    fn start() -> Broker {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let subscriptions = Arc::new(Mutex::new(Vec::new()));
+        let (tx, published) = mpsc::channel();
+        {
+            let subscriptions = subscriptions.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let subscriptions = subscriptions.clone();
+                    let tx = tx.clone();
+                    std::thread::spawn(move || serve(stream.unwrap(), subscriptions, tx));
+                }
+            });
+        }
+        Broker {
+            address,
+            subscriptions,
+            published,
+        }
+    }
+
+    /// Deliver a message to all subscribers of `topic`.
+    fn publish(&self, topic: &str, payload: Value) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while self.subscriptions.lock().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "no subscribers");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        for (filter, stream) in self.subscriptions.lock().unwrap().iter_mut() {
+            if matches(filter, topic) {
+                let mut body = string(topic);
+                body.extend(payload.to_string().into_bytes());
+                write_packet(stream, 0x30, &body);
+            }
+        }
+    }
+
+    /// Wait for a message published on `topic`.
+    fn next(&self, topic: &str) -> Value {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let (t, payload) = self
+                .published
+                .recv_timeout(timeout)
+                .unwrap_or_else(|_| panic!("nothing published on {}", topic));
+            if t == topic {
+                return payload;
+            }
+        }
+    }
+}
+
+fn serve(
+    mut stream: TcpStream,
+    subscriptions: Arc<Mutex<Vec<(String, TcpStream)>>>,
+    published: Sender<(String, Value)>,
+) {
+    while let Some((header, body)) = read_packet(&mut stream) {
+        match header >> 4 {
+            // CONNECT
+            1 => write_packet(&mut stream, 0x20, &[0, 0]),
+            // PUBLISH
+            3 => {
+                let qos = (header >> 1) & 3;
+                let len = u16::from_be_bytes([body[0], body[1]]) as usize;
+                let topic = String::from_utf8(body[2..2 + len].to_vec()).unwrap();
+                let mut payload = &body[2 + len..];
+                if qos > 0 {
+                    write_packet(&mut stream, 0x40, &payload[..2]);
+                    payload = &payload[2..];
+                }
+                let _ = published.send((topic, serde_json::from_slice(payload).unwrap()));
+            }
+            // SUBSCRIBE
+            8 => {
+                let mut ack = body[..2].to_vec();
+                let mut rest = &body[2..];
+                while !rest.is_empty() {
+                    let len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+                    let filter = String::from_utf8(rest[2..2 + len].to_vec()).unwrap();
+                    subscriptions
+                        .lock()
+                        .unwrap()
+                        .push((filter, stream.try_clone().unwrap()));
+                    ack.push(0);
+                    rest = &rest[3 + len..];
+                }
+                write_packet(&mut stream, 0x90, &ack);
+            }
+            // PINGREQ
+            12 => write_packet(&mut stream, 0xd0, &[]),
+            // DISCONNECT
+            14 => return,
+            _ => {}
+        }
+    }
+}
+
+fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let mut byte = [0u8];
+    stream.read_exact(&mut byte).ok()?;
+    let header = byte[0];
+    let (mut len, mut shift) = (0usize, 0);
+    loop {
+        stream.read_exact(&mut byte).ok()?;
+        len |= ((byte[0] & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body).ok()?;
+    Some((header, body))
+}
+
+fn write_packet(stream: &mut TcpStream, header: u8, body: &[u8]) {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        packet.push(if len > 0 { byte | 0x80 } else { byte });
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    let _ = stream.write_all(&packet);
+}
+
+fn string(s: &str) -> Vec<u8> {
+    let mut bytes = (s.len() as u16).to_be_bytes().to_vec();
+    bytes.extend_from_slice(s.as_bytes());
+    bytes
+}
+
+fn matches(filter: &str, topic: &str) -> bool {
+    let mut topic = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(t)) if level == t => {}
+            _ => return false,
+        }
+    }
+    topic.next().is_none()
+}
+
+#[test]
+fn publishes_events_and_executes_commands() {
+    let ip = Ipv4Addr::new(127, 0, 0, 71);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let broker = Broker::start();
+
+    let listener: SocketAddr = "127.0.0.1:61071".parse().unwrap();
+    let config = MqttConfig {
+        broker: broker.address.ip().to_string(),
+        port: broker.address.port(),
+        client_id: "uhppote-test".to_string(),
+        prefix: "uhppote".to_string(),
+        bind: listener,
+        listener,
+        // Nothing answers at 127.0.0.72, which must not keep the other device from being bridged.
+        devices: vec![
+            (DEVICE_ID, Some(ip)),
+            (1, Some(Ipv4Addr::new(127, 0, 0, 72))),
+        ],
+        status_interval: Some(Duration::from_secs(3600)),
+    };
+    let uhppoted = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        "255.255.255.255".parse().unwrap(),
+        Duration::from_millis(500),
+    );
+    let (errors_tx, errors) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            MqttBridge::new(uhppoted, config)
+                .on_error(move |id, e| errors_tx.send((id, e.to_string())).unwrap())
+                .run(&stop)
+                .unwrap()
+        })
+    };
+
+    let status = broker.next("uhppote/423196779/status");
+    assert_eq!(status["device_id"], DEVICE_ID);
+    let (id, _) = errors.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(id, 1);
+
+    broker.publish("uhppote/2/commands/open-door", json!({ "door": 1 }));
+    assert_eq!(
+        broker.next("uhppote/2/replies/open-door"),
+        json!({ "error": "invalid argument: unknown device: 2" })
+    );
+
+    broker.publish(
+        "uhppote/423196779/commands/open-door",
+        json!({ "door": 1, "request_id": "r1" }),
+    );
+    assert_eq!(
+        broker.next("uhppote/423196779/replies/open-door"),
+        json!({ "ok": true, "request_id": "r1" })
+    );
+
+    let card = json!({
+        "number": 12345,
+        "from": "2023-01-01",
+        "to": "2023-12-31",
+        "doors": [1, 0, 0, 0],
+        "pin": null,
+    });
+    broker.publish("uhppote/423196779/commands/add-card", card);
+    assert_eq!(
+        broker.next("uhppote/423196779/replies/add-card"),
+        json!({ "ok": true })
+    );
+    let u = Uhppoted::default();
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    assert_eq!(
+        device.get_card_by_id(12345).unwrap().doors,
+        vec![1, 0, 0, 0]
+    );
+
+    broker.publish(
+        "uhppote/423196779/commands/open-door",
+        json!({ "request_id": "r2" }),
+    );
+    let reply = broker.next("uhppote/423196779/replies/open-door");
+    assert_eq!(reply["request_id"], "r2");
+    assert!(reply["error"].is_string());
+
+    simulator.add_event(swipe(12345)).unwrap();
+    let event = broker.next("uhppote/423196779/events");
+    assert_eq!(event["device_id"], DEVICE_ID);
+    assert_eq!(event["card_number"], 12345);
+
+    stop.store(true, Ordering::Relaxed);
+    thread.join().unwrap();
+}
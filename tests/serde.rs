@@ -0,0 +1,282 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use uhppote_rs::*;
+
+/// Serialize `value` to JSON, check it against `expected` and deserialize it again.
+fn round_trip<T>(value: T, expected: serde_json::Value)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let json = serde_json::to_value(&value).unwrap();
+    assert_eq!(json, expected);
+    assert_eq!(serde_json::from_value::<T>(json).unwrap(), value);
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn event() -> Event {
+    Event {
+        timestamp: date(2023, 5, 4).and_hms_opt(12, 34, 56).unwrap(),
+        index: 57,
+        event_type: EventType::Swipe,
+        granted: true,
+        door: 3,
+        direction: Direction::In,
+        card_number: 8165537,
+        reason: EventReason::Swipe,
+    }
+}
+
+fn event_json() -> serde_json::Value {
+    json!({
+        "timestamp": "2023-05-04T12:34:56",
+        "index": 57,
+        "event_type": "swipe",
+        "granted": true,
+        "door": 3,
+        "direction": "in",
+        "card_number": 8165537,
+        "reason": "swipe",
+    })
+}
+
+#[test]
+fn card() {
+    round_trip(
+        Card {
+            number: 8165537,
+            from: date(2023, 1, 1),
+            to: date(2023, 12, 31),
+            doors: vec![1, 0, 29, 1],
+            pin: Some(7531),
+        },
+        json!({
+            "number": 8165537,
+            "from": "2023-01-01",
+            "to": "2023-12-31",
+            "doors": [1, 0, 29, 1],
+            "pin": 7531,
+        }),
+    );
+}
+
+#[test]
+fn event_and_status() {
+    round_trip(event(), event_json());
+    round_trip(
+        Status {
+            device_id: 423187757,
+            system_time: time(9, 49),
+            system_date: date(2019, 8, 10),
+            doors: vec![false, true, false, false],
+            buttons: vec![false, false, true, false],
+            relay_state: 0,
+            input_state: 0,
+            system_error: 0,
+            special_info: 0,
+            sequence_number: 21,
+            last_event: Some(event()),
+        },
+        json!({
+            "device_id": 423187757,
+            "system_time": "09:49:00",
+            "system_date": "2019-08-10",
+            "doors": [false, true, false, false],
+            "buttons": [false, false, true, false],
+            "relay_state": 0,
+            "input_state": 0,
+            "system_error": 0,
+            "special_info": 0,
+            "sequence_number": 21,
+            "last_event": event_json(),
+        }),
+    );
+}
+
+#[test]
+fn event_enums() {
+    round_trip(EventType::Overwritten, json!("overwritten"));
+    round_trip(EventReason::RemoteOpenDoor, json!("remote_open_door"));
+    round_trip(
+        EventReason::PushbuttonInvalidDoorLocked,
+        json!("pushbutton_invalid_door_locked"),
+    );
+    round_trip(Direction::Out, json!("out"));
+}
+
+#[test]
+fn device_config() {
+    round_trip(
+        DeviceConfig {
+            id: 423187757,
+            address: "192.168.1.100".parse().unwrap(),
+            subnet: "255.255.255.0".parse().unwrap(),
+            gateway: "192.168.1.1".parse().unwrap(),
+            mac: "00:66:19:39:55:2d".to_string(),
+            version: "0892".to_string(),
+            date: date(2018, 8, 16),
+        },
+        json!({
+            "id": 423187757,
+            "address": "192.168.1.100",
+            "subnet": "255.255.255.0",
+            "gateway": "192.168.1.1",
+            "mac": "00:66:19:39:55:2d",
+            "version": "0892",
+            "date": "2018-08-16",
+        }),
+    );
+}
+
+#[test]
+fn door_control() {
+    round_trip(
+        DoorControl {
+            mode: DoorControlMode::NormallyOpen,
+            delay: Duration::from_secs(5),
+        },
+        json!({
+            "mode": "normally_open",
+            "delay": 5,
+        }),
+    );
+    round_trip(DoorControlMode::Controlled, json!("controlled"));
+}
+
+#[test]
+fn time_profile() {
+    let segment = TimeProfileSegment {
+        start: time(8, 30),
+        end: time(17, 0),
+    };
+    round_trip(
+        TimeProfile {
+            id: 29,
+            linked_profile_id: 3,
+            from: date(2023, 1, 1),
+            to: date(2023, 12, 31),
+            monday: true,
+            tuesday: true,
+            wednesday: false,
+            thursday: true,
+            friday: true,
+            saturday: false,
+            sunday: false,
+            segments: [segment; 3],
+        },
+        json!({
+            "id": 29,
+            "linked_profile_id": 3,
+            "from": "2023-01-01",
+            "to": "2023-12-31",
+            "monday": true,
+            "tuesday": true,
+            "wednesday": false,
+            "thursday": true,
+            "friday": true,
+            "saturday": false,
+            "sunday": false,
+            "segments": [
+                { "start": "08:30:00", "end": "17:00:00" },
+                { "start": "08:30:00", "end": "17:00:00" },
+                { "start": "08:30:00", "end": "17:00:00" },
+            ],
+        }),
+    );
+}
+
+#[test]
+fn task() {
+    round_trip(
+        Task {
+            task: TaskID::EnableCardWithPassword,
+            door: 3,
+            from: date(2023, 1, 1),
+            to: date(2023, 12, 31),
+            monday: true,
+            tuesday: false,
+            wednesday: false,
+            thursday: false,
+            friday: true,
+            saturday: false,
+            sunday: false,
+            at: time(8, 45),
+            more_cards: 0,
+        },
+        json!({
+            "task": "enable_card_with_password",
+            "door": 3,
+            "from": "2023-01-01",
+            "to": "2023-12-31",
+            "monday": true,
+            "tuesday": false,
+            "wednesday": false,
+            "thursday": false,
+            "friday": true,
+            "saturday": false,
+            "sunday": false,
+            "at": "08:45:00",
+            "more_cards": 0,
+        }),
+    );
+}
+
+#[test]
+fn door_settings() {
+    round_trip(AntiPassback::Doors12And34, json!("doors_12_34"));
+    round_trip(AntiPassback::Door1And234, json!("door_1_234"));
+    round_trip(Interlock::Doors123, json!("doors_123"));
+    round_trip(Interlock::None, json!("none"));
+    round_trip(
+        FirstCardConfig {
+            door: 2,
+            start: time(8, 0),
+            end: time(18, 0),
+            start_mode: DoorControlMode::NormallyOpen,
+            end_mode: DoorControlMode::Controlled,
+            monday: true,
+            tuesday: true,
+            wednesday: true,
+            thursday: true,
+            friday: true,
+            saturday: false,
+            sunday: false,
+        },
+        json!({
+            "door": 2,
+            "start": "08:00:00",
+            "end": "18:00:00",
+            "start_mode": "normally_open",
+            "end_mode": "controlled",
+            "monday": true,
+            "tuesday": true,
+            "wednesday": true,
+            "thursday": true,
+            "friday": true,
+            "saturday": false,
+            "sunday": false,
+        }),
+    );
+}
+
+#[test]
+fn event_log_entry() {
+    round_trip(
+        EventLogEntry::Overwritten { from: 1, to: 100 },
+        json!({ "kind": "overwritten", "from": 1, "to": 100 }),
+    );
+    let mut expected = event_json();
+    expected["kind"] = json!("event");
+    round_trip(EventLogEntry::Event(event()), expected);
+}
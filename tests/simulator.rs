@@ -0,0 +1,478 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use chrono::NaiveDate;
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+mod common;
+use common::swipe;
+
+const DEVICE_ID: u32 = 423196779;
+
+/// Start a simulator on its own loopback address, so tests can run in parallel.
+fn start(host: u8) -> (Simulator, Uhppoted, Ipv4Addr) {
+    let ip = Ipv4Addr::new(127, 0, 0, host);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    (simulator, Uhppoted::default(), ip)
+}
+
+fn card(number: u32) -> Card {
+    Card {
+        number,
+        from: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+        to: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+        doors: vec![1, 0, 29, 1],
+        pin: Some(7531),
+    }
+}
+
+#[test]
+fn get_config() {
+    let (_simulator, u, ip) = start(11);
+    let config = u.get_device(DEVICE_ID, Some(ip)).get_config().unwrap();
+    assert_eq!(config.id, DEVICE_ID);
+    assert_eq!(config.address, ip);
+}
+
+#[test]
+fn cards() {
+    let (_simulator, u, ip) = start(12);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+
+    device.add_card(card(8165537)).unwrap();
+    device.add_card(card(8165538)).unwrap();
+    assert_eq!(device.get_cards().unwrap(), 2);
+
+    let c = device.get_card_by_id(8165538).unwrap();
+    assert_eq!(c.number, 8165538);
+    assert_eq!(c.doors, vec![1, 0, 29, 1]);
+    assert_eq!(c.pin, Some(7531));
+
+    device.delete_card(8165537).unwrap();
+    assert_eq!(device.get_cards().unwrap(), 1);
+    assert!(matches!(
+        device.delete_card(8165537),
+        Err(Error::Rejected { .. })
+    ));
+    assert_eq!(device.get_card_by_index(2).unwrap().number, 8165538);
+
+    device.clear_cards().unwrap();
+    assert_eq!(device.get_cards().unwrap(), 0);
+}
+
+#[test]
+fn events() {
+    let (simulator, u, ip) = start(13);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+
+    device.open_door(3).unwrap();
+    let event = device.get_event(1).unwrap();
+    assert_eq!(event.index, 1);
+    assert_eq!(event.door, 3);
+    assert!(matches!(event.reason, EventReason::RemoteOpenDoor));
+
+    let index = simulator.add_event(swipe(8165537)).unwrap();
+    assert_eq!(index, 2);
+    assert_eq!(device.get_event(2).unwrap().card_number, 8165537);
+    let status = device.get_status().unwrap();
+    assert_eq!(status.last_event.unwrap().index, 2);
+
+    device.set_event_index(2).unwrap();
+    assert_eq!(device.get_event_index().unwrap(), 2);
+}
+
+#[test]
+fn door_control_and_settings() {
+    let (_simulator, u, ip) = start(14);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+
+    device
+        .set_door_control_state(
+            2,
+            DoorControl {
+                mode: DoorControlMode::NormallyOpen,
+                delay: std::time::Duration::from_secs(7),
+            },
+        )
+        .unwrap();
+    let state = device.get_door_control(2).unwrap();
+    assert!(matches!(state.mode, DoorControlMode::NormallyOpen));
+    assert_eq!(state.delay.as_secs(), 7);
+
+    device.set_antipassback(AntiPassback::Doors13And24).unwrap();
+    assert!(matches!(
+        device.get_antipassback().unwrap(),
+        AntiPassback::Doors13And24
+    ));
+    assert!(matches!(AntiPassback::try_from(5), Err(Error::Decode(_))));
+
+    device
+        .set_listener(Ipv4Addr::new(127, 0, 0, 1), 60001)
+        .unwrap();
+    assert_eq!(
+        device.get_listener().unwrap(),
+        "127.0.0.1:60001".parse().unwrap()
+    );
+}
+
+#[test]
+fn time() {
+    let (_simulator, u, ip) = start(15);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    let datetime = NaiveDate::from_ymd_opt(2022, 3, 4)
+        .unwrap()
+        .and_hms_opt(5, 6, 7)
+        .unwrap();
+    let set = device.set_time(datetime).unwrap();
+    assert!((set - datetime).num_seconds().abs() <= 1);
+    let get = device.get_time().unwrap();
+    assert!((get - datetime).num_seconds().abs() <= 2);
+}
+
+#[test]
+fn ignores_other_devices() {
+    let (_simulator, _, ip) = start(16);
+    let u = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        std::time::Duration::from_millis(200),
+    );
+    let device = u.get_device(DEVICE_ID + 1, Some(ip));
+    let e = device.get_status().unwrap_err();
+    assert!(matches!(e.cause(), Error::Timeout));
+}
+
+#[test]
+fn listener() {
+    let (simulator, u, ip) = start(17);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    let (handle, statuses) = u
+        .listener("127.0.0.1:0".parse().unwrap())
+        .unwrap()
+        .device(DEVICE_ID)
+        .channel()
+        .unwrap();
+    device
+        .set_listener(Ipv4Addr::LOCALHOST, handle.address().port())
+        .unwrap();
+    device.open_door(1).unwrap();
+
+    let status = statuses
+        .recv_timeout(std::time::Duration::from_secs(2))
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.device_id, DEVICE_ID);
+    assert_eq!(status.last_event.unwrap().door, 1);
+
+    handle.stop();
+    simulator.stop();
+}
+
+#[test]
+fn cards_iterator() {
+    let (_simulator, u, ip) = start(18);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    for number in 1..=5 {
+        device.add_card(card(number)).unwrap();
+    }
+    device.delete_card(2).unwrap();
+    device.delete_card(4).unwrap();
+
+    let mut progress = Vec::new();
+    let numbers: Vec<u32> = device
+        .cards()
+        .unwrap()
+        .on_progress(|p| progress.push((p.index, p.found, p.total)))
+        .map(|c| c.unwrap().number)
+        .collect();
+    assert_eq!(numbers, vec![1, 3, 5]);
+    assert_eq!(progress.last(), Some(&(5, 3, 3)));
+
+    let all = device.get_all_cards().unwrap();
+    assert_eq!(all.len(), 3);
+}
+
+#[test]
+fn bulk_download_survives_a_lost_reply() {
+    let (simulator, u, ip) = start(28);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    for number in 1..=3 {
+        device.add_card(card(number)).unwrap();
+    }
+
+    simulator.drop_replies(1);
+    let all = device.get_all_cards().unwrap();
+    assert_eq!(all.len(), 3);
+
+    // Without retries the lost reply ends the download.
+    let mut cards = device.cards().unwrap().retries(0);
+    simulator.drop_replies(1);
+    assert!(matches!(cards.next(), Some(Err(Error::Timeout))));
+}
+
+#[test]
+fn sync_cards() {
+    let (_simulator, u, ip) = start(19);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    device.add_card(card(1)).unwrap();
+    device.add_card(card(2)).unwrap();
+
+    let mut updated = card(2);
+    updated.doors = vec![0, 0, 0, 1];
+    let desired = vec![updated.clone(), card(3)];
+
+    let plan = device.plan_card_sync(desired.clone()).unwrap();
+    assert_eq!(
+        plan.changes,
+        vec![
+            CardChange::Delete(card(1)),
+            CardChange::Update {
+                current: card(2),
+                desired: updated.clone(),
+            },
+            CardChange::Add(card(3)),
+        ]
+    );
+    assert_eq!(device.get_cards().unwrap(), 2);
+
+    let results = device.sync_cards(desired).unwrap();
+    assert!(results.iter().all(|r| r.result.is_ok()));
+    assert_eq!(device.get_all_cards().unwrap(), vec![updated, card(3)]);
+    assert_eq!(device.plan_card_sync(vec![]).unwrap().changes.len(), 2);
+}
+
+#[test]
+fn event_log() {
+    let (simulator, u, ip) = start(20);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    for card in 1..=5 {
+        simulator.add_event(swipe(card)).unwrap();
+    }
+
+    let mut events = device.new_events().unwrap();
+    assert_eq!(events.end_index(), 5);
+    let cards: Vec<u32> = events
+        .by_ref()
+        .map(|e| match e.unwrap() {
+            EventLogEntry::Event(e) => e.card_number,
+            gap => panic!("unexpected {:?}", gap),
+        })
+        .collect();
+    assert_eq!(cards, vec![1, 2, 3, 4, 5]);
+    assert_eq!(events.cursor(), 5);
+    events.commit().unwrap();
+    assert_eq!(device.get_event_index().unwrap(), 5);
+    assert_eq!(device.new_events().unwrap().count(), 0);
+
+    // Resume from a stored cursor after the log wrapped around.
+    for card in 6..=10 {
+        simulator.add_event(swipe(card)).unwrap();
+    }
+    simulator.overwrite_events(7);
+    let entries: Vec<EventLogEntry> = device
+        .events_since(3)
+        .unwrap()
+        .map(|e| e.unwrap())
+        .collect();
+    assert!(matches!(
+        entries[0],
+        EventLogEntry::Overwritten { from: 4, to: 7 }
+    ));
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[3].last_index(), 10);
+}
+
+#[test]
+fn retries() {
+    let (simulator, _, ip) = start(21);
+    let u = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        std::time::Duration::from_secs(5),
+    )
+    .retry_policy(RetryPolicy {
+        attempts: 3,
+        attempt_timeout: Some(std::time::Duration::from_millis(200)),
+        backoff: std::time::Duration::from_millis(10),
+        ..Default::default()
+    });
+    let device = u.get_device(DEVICE_ID, Some(ip));
+
+    // Reads are retried.
+    simulator.drop_replies(2);
+    assert_eq!(device.get_status().unwrap().device_id, DEVICE_ID);
+    assert_eq!(u.request_metrics()["GetStatus"].timeouts, 2);
+
+    simulator.drop_replies(3);
+    match device.get_status() {
+        Err(e @ Error::Retried { attempts: 3, .. }) => {
+            assert!(matches!(e.cause(), Error::Timeout))
+        }
+        r => panic!("expected retries, got {:?}", r),
+    }
+
+    // Downloading cards follows the policy too, without retrying on top of it.
+    device.add_card(card(1)).unwrap();
+    {
+        let mut cards = device.cards().unwrap();
+        simulator.drop_replies(3);
+        assert!(matches!(
+            cards.next(),
+            Some(Err(Error::Retried { attempts: 3, .. }))
+        ));
+    }
+    assert_eq!(u.request_metrics()["GetCardByIndex"].timeouts, 3);
+
+    // Writes are not, unless asked for.
+    simulator.drop_replies(1);
+    assert!(matches!(device.open_door(1), Err(Error::Timeout)));
+
+    let u = u.retry_policy(RetryPolicy {
+        attempts: 3,
+        attempt_timeout: Some(std::time::Duration::from_millis(200)),
+        retry_writes: true,
+        ..Default::default()
+    });
+    simulator.drop_replies(1);
+    u.get_device(DEVICE_ID, Some(ip)).open_door(1).unwrap();
+}
+
+#[test]
+fn concurrent_requests_share_a_socket() {
+    let (_simulator, _, ip) = start(22);
+    let other_ip = Ipv4Addr::new(127, 0, 0, 23);
+    let _other = Simulator::start(DEVICE_ID + 1, SocketAddr::from((other_ip, 60000))).unwrap();
+    // A fixed port only works when all requests share one socket.
+    let u = Uhppoted::new(
+        "127.0.0.1:61022".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        std::time::Duration::from_secs(2),
+    );
+
+    std::thread::scope(|scope| {
+        for i in 0..16 {
+            let u = &u;
+            scope.spawn(move || {
+                let (id, ip) = match i % 2 {
+                    0 => (DEVICE_ID, ip),
+                    _ => (DEVICE_ID + 1, other_ip),
+                };
+                let device = u.get_device(id, Some(ip));
+                for _ in 0..10 {
+                    assert_eq!(device.get_status().unwrap().device_id, id);
+                    assert_eq!(device.get_config().unwrap().id, id);
+                    device.get_time().unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(u.request_metrics()["GetStatus"].responses, 160);
+}
+
+#[test]
+fn overlapping_requests_get_their_own_replies() {
+    let (simulator, _, ip) = start(26);
+    for card_number in [101, 102] {
+        simulator.add_event(swipe(card_number)).unwrap();
+    }
+
+    let u = Uhppoted::new(
+        "0.0.0.0:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        std::time::Duration::from_millis(500),
+    );
+    let device = u.get_device(DEVICE_ID, Some(ip));
+
+    // The reply to the first request is lost, so the only reply while both are waiting belongs
+    // to the second. The first request gets its own event once it is retried.
+    simulator.drop_replies(1);
+    std::thread::scope(|scope| {
+        let first = scope.spawn(|| device.get_event(1));
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let event = device.get_event(2).unwrap();
+        assert_eq!((event.index, event.card_number), (2, 102));
+        let event = first.join().unwrap().unwrap();
+        assert_eq!((event.index, event.card_number), (1, 101));
+    });
+}
+
+#[test]
+fn discover() {
+    let (_first, _, first_ip) = start(24);
+    let second_ip = Ipv4Addr::new(127, 0, 0, 25);
+    let _second = Simulator::start(405419896, SocketAddr::from((second_ip, 60000))).unwrap();
+
+    let u = Uhppoted::new(
+        "127.0.0.1:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        std::time::Duration::from_millis(300),
+    );
+    // The ranges overlap, so the second device answers twice.
+    let devices = u
+        .discover(&[first_ip..=second_ip, second_ip..=second_ip])
+        .unwrap();
+    let found: Vec<_> = devices
+        .iter()
+        .filter(|d| d.config.address.is_loopback())
+        .map(|d| (d.config.id, d.config.address, d.local_address))
+        .collect();
+    let lo = Some(Ipv4Addr::new(127, 0, 0, 1));
+    assert_eq!(
+        found,
+        vec![(405419896, second_ip, lo), (DEVICE_ID, first_ip, lo)]
+    );
+}
+
+#[test]
+fn first_card_door_is_checked() {
+    let transport = std::sync::Arc::new(MockTransport::new(|_| Vec::new()));
+    let u = Uhppoted::default().transport(transport.clone());
+    let config = FirstCardConfig {
+        door: 5,
+        start: chrono::NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        end: chrono::NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+        start_mode: DoorControlMode::NormallyOpen,
+        end_mode: DoorControlMode::Controlled,
+        monday: true,
+        tuesday: true,
+        wednesday: true,
+        thursday: true,
+        friday: true,
+        saturday: false,
+        sunday: false,
+    };
+    assert!(matches!(
+        u.get_device(DEVICE_ID, None).set_first_card(config),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(transport.requests().is_empty());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn async_requests_use_the_uhppoted_stack() {
+    let (simulator, _, ip) = start(27);
+    let u = AsyncUhppoted::from(
+        Uhppoted::new(
+            "0.0.0.0:0".parse().unwrap(),
+            Ipv4Addr::BROADCAST,
+            std::time::Duration::from_secs(5),
+        )
+        .retry_policy(RetryPolicy {
+            attempts: 3,
+            attempt_timeout: Some(std::time::Duration::from_millis(200)),
+            backoff: std::time::Duration::from_millis(10),
+            ..Default::default()
+        }),
+    );
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    simulator.drop_replies(2);
+    let device = u.get_device(DEVICE_ID, Some(ip));
+    let status = runtime.block_on(device.get_status()).unwrap();
+    assert_eq!(status.device_id, DEVICE_ID);
+    assert_eq!(u.request_metrics()["GetStatus"].timeouts, 2);
+}
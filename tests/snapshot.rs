@@ -0,0 +1,106 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveTime};
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+fn profile(id: u8) -> TimeProfile {
+    let segment = TimeProfileSegment {
+        start: NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+    };
+    let empty = TimeProfileSegment {
+        start: NaiveTime::MIN,
+        end: NaiveTime::MIN,
+    };
+    TimeProfile {
+        id,
+        linked_profile_id: 0,
+        from: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+        to: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+        monday: true,
+        tuesday: true,
+        wednesday: true,
+        thursday: true,
+        friday: true,
+        saturday: false,
+        sunday: false,
+        segments: [segment, empty, empty],
+    }
+}
+
+fn card(number: u32, pin: Option<u32>) -> Card {
+    Card {
+        number,
+        from: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+        to: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+        doors: vec![1, 0, 29, 1],
+        pin,
+    }
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let source_ip = Ipv4Addr::new(127, 0, 0, 31);
+    let target_ip = Ipv4Addr::new(127, 0, 0, 32);
+    let _source = Simulator::start(423196779, SocketAddr::from((source_ip, 60000))).unwrap();
+    let _target = Simulator::start(405419896, SocketAddr::from((target_ip, 60000))).unwrap();
+    let u = Uhppoted::default();
+    let source = u.get_device(423196779, Some(source_ip));
+    let target = u.get_device(405419896, Some(target_ip));
+
+    source
+        .set_listener(Ipv4Addr::new(192, 168, 1, 100), 60001)
+        .unwrap();
+    source
+        .set_door_control_state(
+            3,
+            DoorControl {
+                mode: DoorControlMode::NormallyClosed,
+                delay: Duration::from_secs(9),
+            },
+        )
+        .unwrap();
+    source.add_or_update_time_profile(profile(2)).unwrap();
+    source.add_or_update_time_profile(profile(29)).unwrap();
+    source.add_card(card(8165537, None)).unwrap();
+    source.add_card(card(8165538, Some(7531))).unwrap();
+    target.add_card(card(1, None)).unwrap();
+
+    let snapshot = source.snapshot().unwrap();
+    assert_eq!(snapshot.config.id, 423196779);
+    assert_eq!(snapshot.time_profiles, vec![profile(2), profile(29)]);
+    assert_eq!(snapshot.cards.len(), 2);
+
+    target.restore(&snapshot).unwrap();
+    let restored = target.snapshot().unwrap();
+    assert_eq!(restored.listener, snapshot.listener);
+    assert_eq!(restored.doors, snapshot.doors);
+    assert_eq!(restored.time_profiles, snapshot.time_profiles);
+    assert_eq!(restored.cards, snapshot.cards);
+    assert_eq!(restored.config.id, 405419896);
+}
+
+#[test]
+fn json_and_toml() {
+    let ip = Ipv4Addr::new(127, 0, 0, 33);
+    let _simulator = Simulator::start(423196779, SocketAddr::from((ip, 60000))).unwrap();
+    let u = Uhppoted::default();
+    let device = u.get_device(423196779, Some(ip));
+    device.add_or_update_time_profile(profile(2)).unwrap();
+    device.add_card(card(8165537, Some(7531))).unwrap();
+    device.add_card(card(8165538, None)).unwrap();
+
+    let mut snapshot = device.snapshot().unwrap();
+    snapshot.record_special_events = Some(true);
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(
+        serde_json::from_str::<DeviceSnapshot>(&json).unwrap(),
+        snapshot
+    );
+
+    let toml = toml::to_string(&snapshot).unwrap();
+    assert_eq!(toml::from_str::<DeviceSnapshot>(&toml).unwrap(), snapshot);
+}
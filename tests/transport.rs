@@ -0,0 +1,203 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
+
+use uhppote_rs::simulator::Simulator;
+use uhppote_rs::*;
+
+const DEVICE_ID: u32 = 423196779;
+
+/// Send `request` to the simulator at `address` and return its reply, if any.
+fn forward(address: SocketAddr, request: &[u8]) -> Vec<Vec<u8>> {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    socket.send_to(request, address).unwrap();
+    let mut buf = [0u8; 64];
+    match socket.recv(&mut buf) {
+        Ok(n) => vec![buf[..n].to_vec()],
+        Err(_) => Vec::new(),
+    }
+}
+
+#[test]
+fn mock_transport() {
+    let ip = Ipv4Addr::new(127, 0, 0, 91);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let address = simulator.address();
+    let transport = Arc::new(MockTransport::new(move |request| forward(address, request)));
+
+    let u = Uhppoted::default().transport(transport.clone());
+    let device = u.get_device(DEVICE_ID, None);
+    device.open_door(2).unwrap();
+    assert_eq!(device.get_config().unwrap().id, DEVICE_ID);
+    assert_eq!(u.get_device_configs().unwrap().len(), 1);
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|r| r[0] == 0x17));
+
+    // A transport that never replies makes requests time out.
+    let silent = Arc::new(MockTransport::new(|_| Vec::new()));
+    let device = u.get_device(DEVICE_ID, None).transport(silent.clone());
+    let e = device.get_status().unwrap_err();
+    assert!(matches!(e.cause(), Error::Timeout));
+    assert_eq!(silent.requests().len(), 3);
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn udp_transport_keeps_waiting() {
+    // A device that answers every request twice.
+    let device = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let address = device.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 64];
+        while let Ok((_, from)) = device.recv_from(&mut buf) {
+            for sequence in [1, 2] {
+                buf[8] = sequence;
+                device.send_to(&buf, from).unwrap();
+            }
+        }
+    });
+
+    let transport = UdpTransport::unicast("127.0.0.1:0".parse().unwrap(), address).unwrap();
+    let mut request = [0u8; 64];
+    request[..8].copy_from_slice(&[0x17, 0x20, 0, 0, 0x6b, 0x8b, 0x39, 0x19]);
+    let mut received = Vec::new();
+    transport
+        .exchange(&request, Some(Duration::from_secs(1)), &mut |datagram| {
+            received.push(datagram[8]);
+            // Only the second datagram counts as the reply.
+            received.len() == 2
+        })
+        .unwrap();
+    assert_eq!(received, vec![1, 2]);
+}
+
+#[test]
+fn tcp_transport() {
+    let ip = Ipv4Addr::new(127, 0, 0, 92);
+    let simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60000))).unwrap();
+    let address = simulator.address();
+
+    // Relay 64 byte frames between TCP connections and the simulator.
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server_address = server.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in server.incoming() {
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                let mut frame = [0u8; 64];
+                while stream.read_exact(&mut frame).is_ok() {
+                    for reply in forward(address, &frame) {
+                        stream.write_all(&reply).unwrap();
+                    }
+                }
+            });
+        }
+    });
+
+    let u = Uhppoted::new(
+        "127.0.0.1:0".parse().unwrap(),
+        Ipv4Addr::BROADCAST,
+        Duration::from_secs(1),
+    );
+    let device = u
+        .get_device(DEVICE_ID, None)
+        .transport(Arc::new(TcpTransport::new(server_address)));
+    device.set_event_index(7).unwrap();
+    assert_eq!(device.get_event_index().unwrap(), 7);
+    assert_eq!(device.get_config().unwrap().id, DEVICE_ID);
+    assert_eq!(u.request_metrics()["GetConfig"].responses, 1);
+
+    // An explicit address connects there instead of to the registered one, where nothing
+    // listens.
+    let u = u.controllers([Controller {
+        port: server_address.port(),
+        protocol: Protocol::Tcp,
+        ..Controller::new(DEVICE_ID, Ipv4Addr::new(127, 0, 0, 96))
+    }]);
+    let device = u.get_device(DEVICE_ID, Some(Ipv4Addr::LOCALHOST));
+    assert_eq!(device.get_config().unwrap().id, DEVICE_ID);
+}
+
+#[test]
+fn tcp_transport_starts_over_without_a_reply() {
+    // A device that answers every request with two frames, numbered per connection.
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server_address = server.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in server.incoming() {
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                let mut frame = [0u8; 64];
+                let mut sequence = 0;
+                while stream.read_exact(&mut frame).is_ok() {
+                    for _ in 0..2 {
+                        sequence += 1;
+                        frame[8] = sequence;
+                        // The client may have given up on the connection.
+                        let _ = stream.write_all(&frame);
+                    }
+                }
+            });
+        }
+    });
+
+    let transport = TcpTransport::new(server_address);
+    let request = [0x17; 64];
+    let timeout = Some(Duration::from_millis(200));
+    // The first frame is not the reply, and by the time it's handled the request timed out.
+    transport
+        .exchange(&request, timeout, &mut |_| {
+            std::thread::sleep(Duration::from_millis(300));
+            false
+        })
+        .unwrap();
+
+    // The second frame of that request must not be taken for the reply to the next one.
+    let mut first = None;
+    transport
+        .exchange(&request, timeout, &mut |frame| {
+            first.get_or_insert(frame[8]);
+            true
+        })
+        .unwrap();
+    assert_eq!(first, Some(1));
+}
+
+#[test]
+fn registered_controllers() {
+    let ip = Ipv4Addr::new(127, 0, 0, 93);
+    let _simulator = Simulator::start(DEVICE_ID, SocketAddr::from((ip, 60005))).unwrap();
+
+    let u = Uhppoted::new(
+        "127.0.0.1:0".parse().unwrap(),
+        Ipv4Addr::new(127, 0, 0, 94),
+        Duration::from_secs(5),
+    )
+    .controllers([Controller {
+        port: 60005,
+        timeout: Some(Duration::from_millis(100)),
+        ..Controller::new(DEVICE_ID, ip)
+    }]);
+    assert_eq!(
+        u.get_device(DEVICE_ID, None).get_config().unwrap().id,
+        DEVICE_ID
+    );
+    assert_eq!(
+        u.device(DEVICE_ID).unwrap().get_config().unwrap().id,
+        DEVICE_ID
+    );
+    assert!(u.device(DEVICE_ID + 1).is_none());
+
+    // The timeout of the controller applies, not the one of Uhppoted.
+    let started = std::time::Instant::now();
+    let device = u.get_device(DEVICE_ID, Some(Ipv4Addr::new(127, 0, 0, 95)));
+    let e = device.get_status().unwrap_err();
+    assert!(matches!(e.cause(), Error::Timeout));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
@@ -22,6 +22,10 @@ fn impl_request_macro(ast: &syn::DeriveInput) -> TokenStream {
             fn get_id(&self) -> u32 {
                 self.device_id
             }
+
+            fn get_message_type(&self) -> u8 {
+                self.message_type
+            }
         }
     };
     gen.into()
@@ -41,8 +45,23 @@ fn impl_response_macro(ast: &syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
     let gen = quote! {
         impl Response for #name {
-            fn from_bytes(bytes: &[u8; 64]) -> anyhow::Result<Self> {
-                Self::from_bytes_impl(bytes)
+            fn from_bytes(bytes: &[u8; 64]) -> crate::Result<Self> {
+                let response = Self::from_bytes_impl(bytes)?;
+                if response.header != crate::messages::HEADER {
+                    return Err(crate::Error::Decode(format!(
+                        "invalid header: {:#04x}",
+                        response.header
+                    )));
+                }
+                Ok(response)
+            }
+
+            fn get_id(&self) -> u32 {
+                self.device_id
+            }
+
+            fn get_message_type(&self) -> u8 {
+                self.message_type
             }
         }
     };